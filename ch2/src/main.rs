@@ -11,13 +11,15 @@ use winit::{
 
 use window_creator::window::WindowBuilder;
 use wrapper::{
-    buffer::{Buffer, BufferType, DrawType},
-    camera::Camera,
-    shader_program::{Shader, ShaderProgram, ShaderType},
-    vertex_array::VertexArray,
+    camera::Camera, hot_reload::ReloadableProgram, layout::Std140, primitives,
+    shader_program::ShaderType, uniform_buffer::UniformBuffer, vertex_array::Primitive,
 };
 
-mod verticies;
+#[derive(Std140)]
+struct CameraBlock {
+    view: glm::Mat4,
+    projection: glm::Mat4,
+}
 
 fn main() {
     let event_loop = EventLoopBuilder::<()>::with_user_event().build();
@@ -38,51 +40,24 @@ fn main() {
         gl::Enable(gl::DEPTH_TEST);
     }
 
-    let shader_program = ShaderProgram::builder()
-        .attach(Shader::from_file("shaders/vertex.vert", ShaderType::Vertex))
-        .attach(Shader::from_file(
-            "shaders/fragment.frag",
-            ShaderType::Fragment,
-        ))
-        .link();
-
-    let vbo = Buffer::new(BufferType::Array);
-    let vao = VertexArray::new();
-
-    vbo.data::<f32, 216>(verticies::VERTICIES, DrawType::StaticDraw);
-
-    vao.bind();
-
-    VertexArray::vertex_atrrib_pointer::<f32>(0, 3, gl::FALSE, 6, 0);
-    VertexArray::enable_vertex_attrib_array(0);
-    VertexArray::vertex_atrrib_pointer::<f32>(1, 3, gl::FALSE, 6, 3);
-    VertexArray::enable_vertex_attrib_array(1);
-
-    VertexArray::unbind();
-
-    let light_vao = VertexArray::new();
-    light_vao.bind();
+    let mut shader_program = load_program("shaders/vertex.vert", "shaders/fragment.frag");
 
-    vbo.bind();
+    let cube = primitives::cube(1.).into_mesh();
 
-    VertexArray::vertex_atrrib_pointer::<f32>(0, 3, gl::FALSE, 6, 0);
-    VertexArray::enable_vertex_attrib_array(0);
-    VertexArray::vertex_atrrib_pointer::<f32>(1, 3, gl::FALSE, 6, 3);
-    VertexArray::enable_vertex_attrib_array(1);
-
-    VertexArray::unbind();
-
-    let light_shader = ShaderProgram::builder()
-        .attach(Shader::from_file("shaders/vertex.vert", ShaderType::Vertex))
-        .attach(Shader::from_file(
-            "shaders/light.frag",
-            ShaderType::Fragment,
-        ))
-        .link();
+    let mut light_shader = load_program("shaders/vertex.vert", "shaders/light.frag");
 
     let mut cam = Camera::default();
     let projection = glm::perspective(800. / 600., (45f32).to_radians(), 0.1, 100.);
 
+    let camera_block = UniformBuffer::new(&CameraBlock {
+        view: cam.view_matrix(),
+        projection,
+    });
+
+    for program in [&shader_program, &light_shader] {
+        camera_block.attach(program, "Camera").unwrap();
+    }
+
     let mut last_frame = Instant::now();
 
     window
@@ -150,10 +125,8 @@ fn main() {
                     _ => (),
                 }
             }
-            DeviceEvent::MouseMotion { delta } => {
-                if cursor_toggle {
-                    cam.mouse_input(delta);
-                }
+            DeviceEvent::MouseMotion { delta } if cursor_toggle => {
+                cam.mouse_input(delta);
             }
             _ => (),
         },
@@ -192,9 +165,21 @@ fn main() {
 
             cam.process_input(keys_pushed, delta);
 
+            for program in [&mut shader_program, &mut light_shader] {
+                match program.poll() {
+                    // a relinked program starts without block bindings
+                    Some(Ok(())) => camera_block.attach(program, "Camera").unwrap(),
+                    Some(Err(err)) => eprintln!("{err}"),
+                    None => (),
+                }
+            }
+
+            camera_block.set(&CameraBlock {
+                view: cam.view_matrix(),
+                projection,
+            });
+
             shader_program.use_program();
-            shader_program.set_mat4f("view", &cam.view_matrix());
-            shader_program.set_mat4f("projection", &projection);
 
             shader_program.set_vec3f("objectColor", &glm::vec3(1., 0.5, 0.31));
             shader_program.set_vec3f("lightColor", &glm::vec3(1., 1., 1.));
@@ -209,27 +194,22 @@ fn main() {
 
             shader_program.set_mat4f("model", &model);
 
-            vao.bind();
-
-            unsafe {
-                gl::DrawArrays(gl::TRIANGLES, 0, 36);
-            }
+            cube.draw(Primitive::Triangles);
 
             light_shader.use_program();
 
-            light_shader.set_mat4f("view", &cam.view_matrix());
-            light_shader.set_mat4f("projection", &projection);
-
             let mut model = glm::Mat4::identity();
             model = glm::translate(&model, &light_pos);
             model = glm::scale(&model, &glm::vec3(0.2, 0.2, 0.2));
 
             light_shader.set_mat4f("model", &model);
 
-            light_vao.bind();
+            cube.draw(Primitive::Triangles);
 
-            unsafe {
-                gl::DrawArrays(gl::TRIANGLES, 0, 36);
+            for program in [&shader_program, &light_shader] {
+                for err in program.take_uniform_errors() {
+                    eprintln!("{err}");
+                }
             }
 
             egui.paint(&window.window);
@@ -239,3 +219,15 @@ fn main() {
         _ => (),
     });
 }
+
+fn load_program(vertex: &str, fragment: &str) -> ReloadableProgram {
+    ReloadableProgram::builder()
+        .attach(vertex, ShaderType::Vertex)
+        .attach(fragment, ShaderType::Fragment)
+        .binary_cache("target/shader_cache")
+        .link()
+        .unwrap_or_else(|err| {
+            eprintln!("{err}");
+            std::process::exit(1);
+        })
+}
@@ -14,21 +14,12 @@ uniform float specularStrength;
 uniform float ambientStrength;
 uniform uint shininess;
 
-void main() {
-  vec3 ambient = ambientStrength *lightColor;
-
-  vec3 norm = normalize(Normal);
-  vec3 lightDir = normalize(lightPos - FragPos);
+#include "phong.glsl"
 
-  float diff = max(dot(norm, lightDir), 0.0);
-  vec3 diffuse = diff * lightColor;
-
-  vec3 viewDir = normalize(viewPos - FragPos);
-  vec3 reflectDir = reflect(-lightDir, norm);
-  
-  float spec = pow(max(dot(viewDir, reflectDir), 0.0), shininess);
-  vec3 specular = specularStrength * spec * lightColor;
+void main() {
+  vec3 light = phong(Normal, FragPos, viewPos, lightPos, lightColor,
+                     ambientStrength, specularStrength, shininess);
 
-  vec3 result = (ambient + diffuse + specular) * objectColor;
+  vec3 result = light * objectColor;
 	FragColor = vec4(result, 1.0);
 }
@@ -6,9 +6,12 @@ layout (location = 1) in vec3 aNormal;
 out vec3 Normal;
 out vec3 FragPos;
 
+layout (std140) uniform Camera {
+  mat4 view;
+  mat4 projection;
+};
+
 uniform mat4 model;
-uniform mat4 view;
-uniform mat4 projection;
 
 void main() {
    gl_Position = projection * view * model * vec4(aPos, 1.0);
@@ -0,0 +1,104 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Ident, Index, Member};
+
+// `round_to_vec4`: std140 rounds the alignment of structs up to that of a vec4
+pub fn derive(
+    input: TokenStream,
+    trait_name: &str,
+    write: &str,
+    read: Option<&str>,
+    round_to_vec4: bool,
+) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(input)?;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            format!("{trait_name} can only be derived for structs"),
+        ));
+    };
+
+    let (members, types): (Vec<Member>, Vec<_>) = match &data.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|f| (Member::Named(f.ident.clone().unwrap()), &f.ty))
+            .unzip(),
+        Fields::Unnamed(fields) => fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(i, f)| (Member::Unnamed(Index::from(i)), &f.ty))
+            .unzip(),
+        Fields::Unit => (Vec::new(), Vec::new()),
+    };
+
+    let field_vars: Vec<Ident> = (0..members.len())
+        .map(|i| Ident::new(&format!("field_{i}"), Span::call_site()))
+        .collect();
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let trait_ident = Ident::new(trait_name, Span::call_site());
+    let write = Ident::new(write, Span::call_site());
+    let layout = quote!(::wrapper::layout);
+    let layout_trait = quote!(#layout::#trait_ident);
+
+    let align = quote!(#layout::max_align(&[#(<#types as #layout_trait>::ALIGN),*]));
+    let align = match round_to_vec4 {
+        true => quote!(#layout::align_to(#align, 16)),
+        false => align,
+    };
+
+    let read = read.map(|read| {
+        let read = Ident::new(read, Span::call_site());
+
+        quote! {
+            fn #read(bytes: &[u8]) -> Self {
+                let offset = 0usize;
+                #(
+                    let offset = #layout::align_to(offset, <#types as #layout_trait>::ALIGN);
+                    let #field_vars = <#types as #layout_trait>::#read(
+                        &bytes[offset..offset + <#types as #layout_trait>::SIZE],
+                    );
+                    let offset = offset + <#types as #layout_trait>::SIZE;
+                )*
+                let _ = offset;
+
+                Self { #(#members: #field_vars),* }
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics #layout_trait for #name #ty_generics #where_clause {
+            const ALIGN: usize = #align;
+
+            const SIZE: usize = {
+                let offset = 0usize;
+                #(
+                    let offset = #layout::align_to(offset, <#types as #layout_trait>::ALIGN)
+                        + <#types as #layout_trait>::SIZE;
+                )*
+                #layout::align_to(offset, <Self as #layout_trait>::ALIGN)
+            };
+
+            fn #write(&self, out: &mut [u8]) {
+                let offset = 0usize;
+                #(
+                    let offset = #layout::align_to(offset, <#types as #layout_trait>::ALIGN);
+                    #layout_trait::#write(
+                        &self.#members,
+                        &mut out[offset..offset + <#types as #layout_trait>::SIZE],
+                    );
+                    let offset = offset + <#types as #layout_trait>::SIZE;
+                )*
+                let _ = offset;
+            }
+
+            #read
+        }
+    })
+}
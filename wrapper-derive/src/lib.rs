@@ -0,0 +1,43 @@
+use proc_macro::TokenStream;
+
+mod layout;
+mod vertex;
+
+/// Implements `wrapper::layout::Std140` for a struct whose fields all implement it.
+#[proc_macro_derive(Std140)]
+pub fn derive_std140(input: TokenStream) -> TokenStream {
+    layout::derive(input.into(), "Std140", "write_std140", None, true)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `wrapper::layout::Std430` for a struct whose fields all implement it.
+#[proc_macro_derive(Std430)]
+pub fn derive_std430(input: TokenStream) -> TokenStream {
+    layout::derive(
+        input.into(),
+        "Std430",
+        "write_std430",
+        Some("read_std430"),
+        false,
+    )
+    .unwrap_or_else(syn::Error::into_compile_error)
+    .into()
+}
+
+/// Implements `wrapper::vertex_array::Vertex` for a `#[repr(C)]` struct whose
+/// fields all implement `AttributeType`.
+///
+/// Fields get consecutive attribute locations from 0. Field options:
+/// - `#[vertex(location = N)]` moves a field (and the ones after it)
+/// - `#[vertex(normalized)]` maps integers to `[0, 1]` or `[-1, 1]`
+/// - `#[vertex(float)]` converts integers to floats without normalizing them
+/// - `#[vertex(divisor = N)]` advances the attribute once per N instances
+/// - `#[vertex(position)]` marks the position used for bounding boxes, which
+///   is otherwise the field named `position`, if any
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    vertex::derive(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
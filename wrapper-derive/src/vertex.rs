@@ -0,0 +1,121 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Index, LitInt, Member};
+
+pub fn derive(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(input)?;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Vertex can only be derived for structs",
+        ));
+    };
+
+    let fields: Vec<_> = match &data.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|f| (Member::Named(f.ident.clone().unwrap()), f))
+            .collect(),
+        Fields::Unnamed(fields) => fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(i, f)| (Member::Unnamed(Index::from(i)), f))
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+
+    let vertex_array = quote!(::wrapper::vertex_array);
+
+    // locations count up from 0, or from the last explicit `location = N`
+    let mut next_location = 0u32;
+    let mut attributes = Vec::new();
+    let mut position = None;
+
+    for (member, field) in &fields {
+        let ty = &field.ty;
+
+        let mut location = next_location;
+        let mut divisor = 0u32;
+        let mut kind = quote!(<#ty as #vertex_array::AttributeType>::KIND);
+        let mut is_position = false;
+
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("vertex")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("normalized") {
+                    kind = quote!(#vertex_array::AttributeKind::Normalized);
+                    Ok(())
+                } else if meta.path.is_ident("float") {
+                    kind = quote!(#vertex_array::AttributeKind::Float);
+                    Ok(())
+                } else if meta.path.is_ident("location") {
+                    location = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+                    Ok(())
+                } else if meta.path.is_ident("divisor") {
+                    divisor = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+                    Ok(())
+                } else if meta.path.is_ident("position") {
+                    is_position = true;
+                    Ok(())
+                } else {
+                    Err(meta.error(
+                        "expected `normalized`, `float`, `position`, `location = N` or `divisor = N`",
+                    ))
+                }
+            })?;
+        }
+
+        // an explicit `#[vertex(position)]` wins over a field named `position`
+        if is_position {
+            if position.as_ref().is_some_and(|(explicit, _)| *explicit) {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "only one field can be marked `#[vertex(position)]`",
+                ));
+            }
+
+            position = Some((true, member.clone()));
+        } else if position.is_none()
+            && matches!(member, Member::Named(ident) if ident == "position")
+        {
+            position = Some((false, member.clone()));
+        }
+
+        next_location = location + 1;
+
+        attributes.push(quote! {
+            #vertex_array::VertexAttribute {
+                location: #location,
+                components: <#ty as #vertex_array::AttributeType>::COMPONENTS,
+                gl_type: <#ty as #vertex_array::AttributeType>::gl_type(),
+                kind: #kind,
+                offset: ::core::mem::offset_of!(Self, #member),
+                divisor: #divisor,
+            }
+        });
+    }
+
+    let position = position.map(|(_, member)| {
+        quote! {
+            fn position(&self) -> ::core::option::Option<#vertex_array::Position> {
+                let position: [f32; 3] = ::core::convert::Into::into(self.#member);
+                ::core::option::Option::Some(::core::convert::From::from(position))
+            }
+        }
+    });
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #vertex_array::Vertex for #name #ty_generics #where_clause {
+            fn attributes() -> ::std::vec::Vec<#vertex_array::VertexAttribute> {
+                ::std::vec![#(#attributes),*]
+            }
+
+            #position
+        }
+    })
+}
@@ -1,24 +1,168 @@
-use std::ffi::c_void;
+use std::{
+    collections::BTreeSet,
+    ffi::c_void,
+    mem,
+    ops::{Deref, DerefMut},
+    slice,
+    sync::Mutex,
+};
 
-use gl::types::GLenum;
+use bytemuck::Pod;
+use gl::types::{GLbitfield, GLenum};
+
+use crate::error::{Error, Result};
 
 pub enum BufferType {
     Array,
     ElementArray,
+    Uniform,
+    ShaderStorage,
+    CopyRead,
+    CopyWrite,
+    PixelPack,
+    PixelUnpack,
+    DrawIndirect,
+    DispatchIndirect,
+    TransformFeedback,
+    Texture,
+    AtomicCounter,
 }
 
+/// Usage hint: how often the data is specified (static: once, dynamic:
+/// repeatedly, stream: about once per use) and who reads it (draw: GL,
+/// read: the application, copy: GL, from data GL wrote).
 pub enum DrawType {
     StaticDraw,
+    StaticRead,
+    StaticCopy,
+    DynamicDraw,
+    DynamicRead,
+    DynamicCopy,
+    StreamDraw,
+    StreamRead,
+    StreamCopy,
+}
+
+impl DrawType {
+    pub fn resolve(&self) -> GLenum {
+        match self {
+            Self::StaticDraw => gl::STATIC_DRAW,
+            Self::StaticRead => gl::STATIC_READ,
+            Self::StaticCopy => gl::STATIC_COPY,
+            Self::DynamicDraw => gl::DYNAMIC_DRAW,
+            Self::DynamicRead => gl::DYNAMIC_READ,
+            Self::DynamicCopy => gl::DYNAMIC_COPY,
+            Self::StreamDraw => gl::STREAM_DRAW,
+            Self::StreamRead => gl::STREAM_READ,
+            Self::StreamCopy => gl::STREAM_COPY,
+        }
+    }
+}
+
+impl BufferType {
+    /// Whether the target has indexed binding points, see [`Buffer::bind_base`].
+    pub fn is_indexed(&self) -> bool {
+        matches!(
+            self,
+            Self::Uniform | Self::ShaderStorage | Self::TransformFeedback | Self::AtomicCounter
+        )
+    }
+
+    /// Alignment required for offsets passed to `glBindBufferRange`.
+    pub(crate) fn offset_alignment(&self) -> usize {
+        let parameter = match self {
+            Self::Uniform => gl::UNIFORM_BUFFER_OFFSET_ALIGNMENT,
+            Self::ShaderStorage => gl::SHADER_STORAGE_BUFFER_OFFSET_ALIGNMENT,
+            // offsets into these are in units of uint
+            Self::TransformFeedback | Self::AtomicCounter => return 4,
+            _ => return 1,
+        };
+
+        let mut alignment = 1;
+
+        unsafe {
+            gl::GetIntegerv(parameter, &mut alignment);
+        }
+
+        alignment.max(1) as usize
+    }
+}
+
+/// Flags for immutable storage allocated with [`Buffer::storage`], named after
+/// the `GL_*_BIT` they stand for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageFlag {
+    /// Allows updates through [`Buffer::sub_data`].
+    DynamicStorage,
+    MapRead,
+    MapWrite,
+    /// The buffer may stay mapped while GL is using it.
+    MapPersistent,
+    /// Writes through a persistent mapping become visible to GL without a
+    /// memory barrier.
+    MapCoherent,
+    ClientStorage,
+}
+
+impl StorageFlag {
+    pub fn resolve(&self) -> GLbitfield {
+        match self {
+            Self::DynamicStorage => gl::DYNAMIC_STORAGE_BIT,
+            Self::MapRead => gl::MAP_READ_BIT,
+            Self::MapWrite => gl::MAP_WRITE_BIT,
+            Self::MapPersistent => gl::MAP_PERSISTENT_BIT,
+            Self::MapCoherent => gl::MAP_COHERENT_BIT,
+            Self::ClientStorage => gl::CLIENT_STORAGE_BIT,
+        }
+    }
+}
+
+/// Access flags for [`Buffer::map_read`] and the other mapping calls, named
+/// after the `GL_MAP_*_BIT` they stand for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapAccess {
+    Read,
+    Write,
+    Persistent,
+    Coherent,
+    InvalidateRange,
+    InvalidateBuffer,
+    FlushExplicit,
+    Unsynchronized,
+}
+
+impl MapAccess {
+    pub fn resolve(&self) -> GLbitfield {
+        match self {
+            Self::Read => gl::MAP_READ_BIT,
+            Self::Write => gl::MAP_WRITE_BIT,
+            Self::Persistent => gl::MAP_PERSISTENT_BIT,
+            Self::Coherent => gl::MAP_COHERENT_BIT,
+            Self::InvalidateRange => gl::MAP_INVALIDATE_RANGE_BIT,
+            Self::InvalidateBuffer => gl::MAP_INVALIDATE_BUFFER_BIT,
+            Self::FlushExplicit => gl::MAP_FLUSH_EXPLICIT_BIT,
+            Self::Unsynchronized => gl::MAP_UNSYNCHRONIZED_BIT,
+        }
+    }
 }
 
 pub struct Buffer {
     id: u32,
     t: BufferType,
+    // bytes allocated by the last `data` or `storage` call
+    size: usize,
+    // storage allocated with `glBufferStorage` can't be reallocated
+    immutable: bool,
 }
 
 impl Buffer {
     pub fn new(t: BufferType) -> Self {
-        let mut buffer = Buffer { id: 0, t };
+        let mut buffer = Buffer {
+            id: 0,
+            t,
+            size: 0,
+            immutable: false,
+        };
 
         unsafe { gl::GenBuffers(1, &mut buffer.id) };
 
@@ -29,6 +173,17 @@ impl Buffer {
         match t {
             BufferType::Array => gl::ARRAY_BUFFER,
             BufferType::ElementArray => gl::ELEMENT_ARRAY_BUFFER,
+            BufferType::Uniform => gl::UNIFORM_BUFFER,
+            BufferType::ShaderStorage => gl::SHADER_STORAGE_BUFFER,
+            BufferType::CopyRead => gl::COPY_READ_BUFFER,
+            BufferType::CopyWrite => gl::COPY_WRITE_BUFFER,
+            BufferType::PixelPack => gl::PIXEL_PACK_BUFFER,
+            BufferType::PixelUnpack => gl::PIXEL_UNPACK_BUFFER,
+            BufferType::DrawIndirect => gl::DRAW_INDIRECT_BUFFER,
+            BufferType::DispatchIndirect => gl::DISPATCH_INDIRECT_BUFFER,
+            BufferType::TransformFeedback => gl::TRANSFORM_FEEDBACK_BUFFER,
+            BufferType::Texture => gl::TEXTURE_BUFFER,
+            BufferType::AtomicCounter => gl::ATOMIC_COUNTER_BUFFER,
         }
     }
 
@@ -38,20 +193,393 @@ impl Buffer {
         };
     }
 
-    pub fn data<T, const SIZE: usize>(&self, data: [T; SIZE], t: DrawType) {
+    pub fn buffer_type(&self) -> &BufferType {
+        &self.t
+    }
+
+    /// Size of the buffer's storage in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// (Re)allocates the buffer and fills it with `data`. Fails once the
+    /// buffer has immutable storage.
+    pub fn data<T: Pod>(&mut self, data: &[T], t: DrawType) -> Result<()> {
+        self.data_bytes(bytemuck::cast_slice(data), t)
+    }
+
+    /// Overwrites part of the buffer, starting `offset` bytes in. Fails if the
+    /// update doesn't fit into the storage allocated by [`Buffer::data`].
+    pub fn sub_data<T: Pod>(&self, offset: usize, data: &[T]) -> Result<()> {
+        let bytes: &[u8] = bytemuck::cast_slice(data);
+
+        self.check_range(offset, bytes.len())?;
+        self.sub_data_bytes(offset, bytes);
+
+        Ok(())
+    }
+
+    /// Allocates immutable storage initialised with `data`. Its size is fixed
+    /// from now on, and it can only be updated as allowed by `flags`. Fails if
+    /// the buffer already has immutable storage.
+    pub fn storage<T: Pod>(&mut self, data: &[T], flags: &[StorageFlag]) -> Result<()> {
+        let bytes: &[u8] = bytemuck::cast_slice(data);
+        self.storage_raw(bytes.len(), bytes.as_ptr() as *const c_void, flags)
+    }
+
+    /// Allocates `size` bytes of uninitialised immutable storage.
+    pub fn allocate_storage(&mut self, size: usize, flags: &[StorageFlag]) -> Result<()> {
+        self.storage_raw(size, std::ptr::null(), flags)
+    }
+
+    fn storage_raw(
+        &mut self,
+        size: usize,
+        data: *const c_void,
+        flags: &[StorageFlag],
+    ) -> Result<()> {
+        // GL rejects the call, so `size` has to stay what it was
+        if self.immutable {
+            return Err(Error::ImmutableBuffer);
+        }
+
+        self.size = size;
+        self.immutable = true;
+        self.bind();
+
+        unsafe {
+            gl::BufferStorage(
+                Self::resolve_type(&self.t),
+                size as isize,
+                data,
+                flags.iter().fold(0, |bits, flag| bits | flag.resolve()),
+            );
+        };
+
+        Ok(())
+    }
+
+    /// Maps `len` elements of `T` for reading, starting `offset` bytes into
+    /// the buffer. `extra` adds flags like [`MapAccess::Persistent`] to
+    /// [`MapAccess::Read`]. The range is unmapped when the guard is dropped.
+    ///
+    /// Panics if `offset` isn't a multiple of the alignment of `T`.
+    pub fn map_read<T: Pod>(
+        &mut self,
+        offset: usize,
+        len: usize,
+        extra: &[MapAccess],
+    ) -> Result<ReadMapping<'_, T>> {
+        let range = self.map_typed(offset, len, &[MapAccess::Read], extra)?;
+        Ok(ReadMapping(range))
+    }
+
+    /// Maps `len` elements of `T` for writing only. Write-only mappings can
+    /// be faster, but their contents are undefined until written, so the
+    /// guard can only be written to.
+    pub fn map_write<T: Pod>(
+        &mut self,
+        offset: usize,
+        len: usize,
+        extra: &[MapAccess],
+    ) -> Result<WriteMapping<'_, T>> {
+        let range = self.map_typed(offset, len, &[MapAccess::Write], extra)?;
+        Ok(WriteMapping(range))
+    }
+
+    /// Maps `len` elements of `T` for reading and writing.
+    pub fn map_read_write<T: Pod>(
+        &mut self,
+        offset: usize,
+        len: usize,
+        extra: &[MapAccess],
+    ) -> Result<Mapping<'_, T>> {
+        let range = self.map_typed(offset, len, &[MapAccess::Read, MapAccess::Write], extra)?;
+        Ok(Mapping(range))
+    }
+
+    fn map_typed<T: Pod>(
+        &mut self,
+        offset: usize,
+        len: usize,
+        access: &[MapAccess],
+        extra: &[MapAccess],
+    ) -> Result<MappedRange<'_, T>> {
+        // mappings start at least 64 byte aligned relative to the buffer, so
+        // an aligned offset gives an aligned pointer
+        assert_eq!(offset % mem::align_of::<T>(), 0, "misaligned mapping");
+
+        let bytes = len
+            .checked_mul(mem::size_of::<T>())
+            .ok_or(Error::BufferOutOfRange {
+                offset,
+                len: usize::MAX,
+                size: self.size,
+            })?;
+
+        let access: Vec<_> = access.iter().chain(extra).copied().collect();
+        let ptr = self.map_bytes(offset, bytes, &access)?;
+
+        Ok(MappedRange {
+            buffer: self,
+            ptr: ptr as *mut T,
+            len,
+        })
+    }
+
+    pub(crate) fn map_bytes(
+        &self,
+        offset: usize,
+        len: usize,
+        access: &[MapAccess],
+    ) -> Result<*mut u8> {
+        self.check_range(offset, len)?;
+        self.bind();
+
+        let ptr = unsafe {
+            gl::MapBufferRange(
+                Self::resolve_type(&self.t),
+                offset as isize,
+                len as isize,
+                access
+                    .iter()
+                    .fold(0, |bits, access| bits | access.resolve()),
+            )
+        };
+
+        match ptr.is_null() {
+            true => Err(Error::BufferMap),
+            false => Ok(ptr as *mut u8),
+        }
+    }
+
+    pub(crate) fn unmap(&self) {
+        self.bind();
+
+        unsafe {
+            gl::UnmapBuffer(Self::resolve_type(&self.t));
+        }
+    }
+
+    fn check_range(&self, offset: usize, len: usize) -> Result<()> {
+        match offset.checked_add(len).is_none_or(|end| end > self.size) {
+            true => Err(Error::BufferOutOfRange {
+                offset,
+                len,
+                size: self.size,
+            }),
+            false => Ok(()),
+        }
+    }
+
+    pub(crate) fn data_bytes(&mut self, bytes: &[u8], t: DrawType) -> Result<()> {
+        if self.immutable {
+            return Err(Error::ImmutableBuffer);
+        }
+
+        self.size = bytes.len();
         self.bind();
 
         unsafe {
             gl::BufferData(
                 Self::resolve_type(&self.t),
-                (SIZE * std::mem::size_of::<T>()) as isize,
-                &data[0] as *const T as *const c_void,
-                match t {
-                    DrawType::StaticDraw => gl::STATIC_DRAW,
-                },
+                bytes.len() as isize,
+                bytes.as_ptr() as *const c_void,
+                t.resolve(),
+            );
+        };
+
+        Ok(())
+    }
+
+    pub(crate) fn sub_data_bytes(&self, offset: usize, bytes: &[u8]) {
+        self.bind();
+
+        unsafe {
+            gl::BufferSubData(
+                Self::resolve_type(&self.t),
+                offset as isize,
+                bytes.len() as isize,
+                bytes.as_ptr() as *const c_void,
             );
         };
     }
+
+    pub(crate) fn read_bytes(&self, offset: usize, len: usize) -> Vec<u8> {
+        let mut bytes = vec![0; len];
+
+        self.bind();
+
+        unsafe {
+            gl::GetBufferSubData(
+                Self::resolve_type(&self.t),
+                offset as isize,
+                len as isize,
+                bytes.as_mut_ptr() as *mut c_void,
+            );
+        };
+
+        bytes
+    }
+
+    /// Copies `len` bytes from `src_offset` in this buffer to `dst_offset` in
+    /// `other`, on the GPU. Within one buffer the ranges must not overlap.
+    pub fn copy_to(
+        &self,
+        other: &Buffer,
+        src_offset: usize,
+        dst_offset: usize,
+        len: usize,
+    ) -> Result<()> {
+        self.check_range(src_offset, len)?;
+        other.check_range(dst_offset, len)?;
+
+        // both ranges are in bounds, so the ends can't overflow
+        if self.id == other.id && src_offset < dst_offset + len && dst_offset < src_offset + len {
+            return Err(Error::OverlappingCopy {
+                src_offset,
+                dst_offset,
+                len,
+            });
+        }
+
+        unsafe {
+            gl::BindBuffer(gl::COPY_READ_BUFFER, self.id);
+            gl::BindBuffer(gl::COPY_WRITE_BUFFER, other.id);
+            gl::CopyBufferSubData(
+                gl::COPY_READ_BUFFER,
+                gl::COPY_WRITE_BUFFER,
+                src_offset as isize,
+                dst_offset as isize,
+                len as isize,
+            );
+        };
+
+        Ok(())
+    }
+
+    /// Binds the whole buffer to an indexed binding point of its target, which
+    /// has to be one of the [indexed](BufferType::is_indexed) ones.
+    pub fn bind_base(&self, index: u32) -> Result<()> {
+        if !self.t.is_indexed() {
+            return Err(Error::BufferNotIndexed);
+        }
+
+        unsafe {
+            gl::BindBufferBase(Self::resolve_type(&self.t), index, self.id);
+        };
+
+        Ok(())
+    }
+
+    /// Binds `len` bytes starting at `offset` to an indexed binding point.
+    /// `offset` has to be a multiple of the target's offset alignment
+    /// (`GL_UNIFORM_BUFFER_OFFSET_ALIGNMENT`, ...).
+    pub fn bind_range(&self, index: u32, offset: usize, len: usize) -> Result<()> {
+        if !self.t.is_indexed() {
+            return Err(Error::BufferNotIndexed);
+        }
+
+        let alignment = self.t.offset_alignment();
+
+        if !offset.is_multiple_of(alignment) {
+            return Err(Error::MisalignedOffset { offset, alignment });
+        }
+
+        self.check_range(offset, len)?;
+
+        unsafe {
+            gl::BindBufferRange(
+                Self::resolve_type(&self.t),
+                index,
+                self.id,
+                offset as isize,
+                len as isize,
+            );
+        };
+
+        Ok(())
+    }
+}
+
+// a typed range of a mapped buffer, unmapped when dropped
+struct MappedRange<'a, T> {
+    buffer: &'a Buffer,
+    ptr: *mut T,
+    len: usize,
+}
+
+impl<T> Drop for MappedRange<'_, T> {
+    fn drop(&mut self) {
+        self.buffer.unmap();
+    }
+}
+
+/// A range of a [`Buffer`] mapped for reading, from [`Buffer::map_read`].
+pub struct ReadMapping<'a, T>(MappedRange<'a, T>);
+
+impl<T> Deref for ReadMapping<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.0.ptr, self.0.len) }
+    }
+}
+
+/// A range of a [`Buffer`] mapped for writing only, from
+/// [`Buffer::map_write`].
+pub struct WriteMapping<'a, T>(MappedRange<'a, T>);
+
+impl<T: Pod> WriteMapping<'_, T> {
+    pub fn len(&self) -> usize {
+        self.0.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.len == 0
+    }
+
+    /// Panics if `index` is out of range.
+    pub fn write(&mut self, index: usize, value: T) {
+        assert!(index < self.0.len, "index {index} out of range");
+
+        unsafe {
+            self.0.ptr.add(index).write(value);
+        }
+    }
+
+    /// Writes `values` from element `start` on. Panics if they don't fit.
+    pub fn write_slice(&mut self, start: usize, values: &[T]) {
+        assert!(
+            start
+                .checked_add(values.len())
+                .is_some_and(|end| end <= self.0.len),
+            "{} values at {start} out of range",
+            values.len()
+        );
+
+        unsafe {
+            std::ptr::copy_nonoverlapping(values.as_ptr(), self.0.ptr.add(start), values.len());
+        }
+    }
+}
+
+/// A range of a [`Buffer`] mapped for reading and writing, from
+/// [`Buffer::map_read_write`].
+pub struct Mapping<'a, T>(MappedRange<'a, T>);
+
+impl<T> Deref for Mapping<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.0.ptr, self.0.len) }
+    }
+}
+
+impl<T> DerefMut for Mapping<'_, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.0.ptr, self.0.len) }
+    }
 }
 
 impl Drop for Buffer {
@@ -61,3 +589,74 @@ impl Drop for Buffer {
         }
     }
 }
+
+/// Indexed binding points in use by buffers, whether handed out by the pool
+/// or asked for explicitly.
+pub(crate) struct BindingPool(Mutex<BTreeSet<u32>>);
+
+impl BindingPool {
+    const fn new() -> Self {
+        Self(Mutex::new(BTreeSet::new()))
+    }
+}
+
+pub(crate) static UNIFORM_BINDINGS: BindingPool = BindingPool::new();
+pub(crate) static SHADER_STORAGE_BINDINGS: BindingPool = BindingPool::new();
+
+/// A binding point taken from a [`BindingPool`], given back when dropped.
+pub(crate) struct Binding {
+    pool: &'static BindingPool,
+    index: u32,
+}
+
+impl Binding {
+    /// The lowest binding point that isn't in use.
+    pub(crate) fn acquire(pool: &'static BindingPool) -> Self {
+        let mut used = pool.0.lock().unwrap();
+        let index = (0..).find(|b| !used.contains(b)).unwrap();
+
+        used.insert(index);
+
+        Self { pool, index }
+    }
+
+    /// A fixed binding point, which fails if another buffer already uses it.
+    pub(crate) fn reserve(pool: &'static BindingPool, index: u32) -> Result<Self> {
+        match pool.0.lock().unwrap().insert(index) {
+            true => Ok(Self { pool, index }),
+            false => Err(Error::BindingInUse(index)),
+        }
+    }
+
+    pub(crate) fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+impl Drop for Binding {
+    fn drop(&mut self) {
+        self.pool.0.lock().unwrap().remove(&self.index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_bindings_are_not_handed_out() {
+        static POOL: BindingPool = BindingPool::new();
+
+        let fixed = Binding::reserve(&POOL, 0).unwrap();
+        let acquired = Binding::acquire(&POOL);
+        assert_eq!(acquired.index(), 1);
+
+        assert!(matches!(
+            Binding::reserve(&POOL, 1),
+            Err(Error::BindingInUse(1))
+        ));
+
+        drop(fixed);
+        assert_eq!(Binding::acquire(&POOL).index(), 0);
+    }
+}
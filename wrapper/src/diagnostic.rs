@@ -0,0 +1,299 @@
+use std::{fmt, path::PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "error" | "fatal error" => Some(Self::Error),
+            "warning" => Some(Self::Warning),
+            "info" | "note" => Some(Self::Info),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+        })
+    }
+}
+
+/// A single message from a shader info log.
+///
+/// `source` is the GLSL source string number the driver reported, `file` is that
+/// number resolved against the files the shader was built from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub file: Option<PathBuf>,
+    pub source: Option<u32>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, self.source) {
+            (Some(file), _) => write!(f, "{}:", file.display())?,
+            (None, Some(source)) => write!(f, "{source}:")?,
+            (None, None) => (),
+        }
+
+        if let Some(line) = self.line {
+            write!(f, "{line}:")?;
+
+            if let Some(column) = self.column {
+                write!(f, "{column}:")?;
+            }
+        }
+
+        if self.file.is_some() || self.source.is_some() {
+            f.write_str(" ")?;
+        }
+
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// Parses a compile info log into diagnostics.
+///
+/// Understands the Mesa (`0:12(5): error: ...`), NVIDIA (`0(12) : error C0000: ...`)
+/// and AMD/Intel (`ERROR: 0:12: ...`) formats. `files[n]` is the file that source
+/// string `n` came from. Lines that match none of the formats are appended to the
+/// previous diagnostic.
+pub fn parse_log(log: &str, files: &[PathBuf]) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    for line in log.lines() {
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        let parsed = parse_mesa(trimmed)
+            .or_else(|| parse_nvidia(trimmed))
+            .or_else(|| parse_amd(trimmed));
+
+        match (parsed, diagnostics.last_mut()) {
+            (Some(mut diagnostic), _) => {
+                diagnostic.file = diagnostic
+                    .source
+                    .and_then(|source| files.get(source as usize))
+                    .cloned();
+
+                diagnostics.push(diagnostic);
+            }
+            (None, Some(last)) => {
+                last.message.push('\n');
+                last.message.push_str(trimmed);
+            }
+            (None, None) => diagnostics.push(Diagnostic {
+                file: None,
+                source: None,
+                line: None,
+                column: None,
+                severity: Severity::Error,
+                message: trimmed.to_owned(),
+            }),
+        }
+    }
+
+    diagnostics
+}
+
+// 0:12(5): error: message
+fn parse_mesa(line: &str) -> Option<Diagnostic> {
+    let (source, rest) = line.split_once(':')?;
+    let source = source.parse().ok()?;
+
+    let (line_no, rest) = rest.split_once('(')?;
+    let line_no = line_no.parse().ok()?;
+
+    let (column, rest) = rest.split_once(')')?;
+    let column = column.parse().ok()?;
+
+    let (severity, message) = rest.strip_prefix(':')?.split_once(':')?;
+
+    Some(Diagnostic {
+        file: None,
+        source: Some(source),
+        line: Some(line_no),
+        column: Some(column),
+        severity: Severity::parse(severity)?,
+        message: message.trim().to_owned(),
+    })
+}
+
+// 0(12) : error C0000: message
+fn parse_nvidia(line: &str) -> Option<Diagnostic> {
+    let (source, rest) = line.split_once('(')?;
+    let source = source.trim().parse().ok()?;
+
+    let (line_no, rest) = rest.split_once(')')?;
+    let line_no = line_no.parse().ok()?;
+
+    let (severity, message) = rest.trim_start().strip_prefix(':')?.split_once(':')?;
+
+    // "error C0000", the code is kept as part of the message
+    let mut words = severity.split_whitespace();
+    let severity = Severity::parse(words.next()?)?;
+    let message = match words.next() {
+        Some(code) => format!("{code}: {}", message.trim()),
+        None => message.trim().to_owned(),
+    };
+
+    Some(Diagnostic {
+        file: None,
+        source: Some(source),
+        line: Some(line_no),
+        column: None,
+        severity,
+        message,
+    })
+}
+
+// ERROR: 0:12: message
+fn parse_amd(line: &str) -> Option<Diagnostic> {
+    let (severity, rest) = line.split_once(':')?;
+    let severity = Severity::parse(severity)?;
+
+    let location = rest
+        .trim_start()
+        .split_once(':')
+        .and_then(|(source, rest)| {
+            let source = source.parse().ok()?;
+            let (line_no, message) = rest.split_once(':')?;
+
+            Some((source, line_no.parse().ok()?, message))
+        });
+
+    Some(match location {
+        Some((source, line_no, message)) => Diagnostic {
+            file: None,
+            source: Some(source),
+            line: Some(line_no),
+            column: None,
+            severity,
+            message: message.trim().to_owned(),
+        },
+        None => Diagnostic {
+            file: None,
+            source: None,
+            line: None,
+            column: None,
+            severity,
+            message: rest.trim().to_owned(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files() -> Vec<PathBuf> {
+        vec![PathBuf::from("main.frag"), PathBuf::from("lighting.glsl")]
+    }
+
+    #[test]
+    fn mesa() {
+        let diagnostics = parse_log("0:12(5): error: `foo' undeclared\n", &files());
+
+        assert_eq!(
+            diagnostics,
+            [Diagnostic {
+                file: Some(PathBuf::from("main.frag")),
+                source: Some(0),
+                line: Some(12),
+                column: Some(5),
+                severity: Severity::Error,
+                message: "`foo' undeclared".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn nvidia() {
+        let diagnostics = parse_log(
+            "1(7) : warning C7050: \"color\" might be used before being initialized",
+            &files(),
+        );
+
+        assert_eq!(
+            diagnostics,
+            [Diagnostic {
+                file: Some(PathBuf::from("lighting.glsl")),
+                source: Some(1),
+                line: Some(7),
+                column: None,
+                severity: Severity::Warning,
+                message: "C7050: \"color\" might be used before being initialized".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn amd() {
+        let log = "ERROR: 0:3: 'vec5' : syntax error syntax error\n\
+                   ERROR: 1 compilation errors.  No code generated.\n";
+        let diagnostics = parse_log(log, &files());
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].file, Some(PathBuf::from("main.frag")));
+        assert_eq!(diagnostics[0].line, Some(3));
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].message, "'vec5' : syntax error syntax error");
+
+        // the summary line has no location
+        assert_eq!(diagnostics[1].source, None);
+        assert_eq!(
+            diagnostics[1].message,
+            "1 compilation errors.  No code generated."
+        );
+    }
+
+    #[test]
+    fn continuation_lines_join_the_previous_message() {
+        let log = "0:4(1): error: no matching function for call to `mix(float)'\n\
+                   \x20   candidates are: float mix(float, float, float)\n";
+        let diagnostics = parse_log(log, &files());
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "no matching function for call to `mix(float)'\n\
+             candidates are: float mix(float, float, float)"
+        );
+    }
+
+    #[test]
+    fn unknown_source_numbers_have_no_file() {
+        let diagnostics = parse_log("5:1(1): error: oops", &files());
+
+        assert_eq!(diagnostics[0].source, Some(5));
+        assert_eq!(diagnostics[0].file, None);
+        assert_eq!(diagnostics[0].to_string(), "5:1:1: error: oops");
+    }
+
+    #[test]
+    fn display_uses_the_file_name() {
+        let diagnostics = parse_log("1:2(3): warning: unused", &files());
+
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "lighting.glsl:2:3: warning: unused"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use std::{ffi::NulError, fmt, io, path::PathBuf};
+
+use gl::types::GLenum;
+
+use gltf::Error as GltfError;
+use image::ImageError;
+
+use crate::{diagnostic::Diagnostic, shader_program::ShaderType, uniform};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Image(ImageError),
+    Gltf(GltfError),
+    UnsupportedUvSet {
+        material: Option<String>,
+        uv_set: u32,
+    },
+    Nul(NulError),
+    ShaderCompile {
+        stage: ShaderType,
+        log: String,
+        diagnostics: Vec<Diagnostic>,
+    },
+    ProgramLink {
+        log: String,
+    },
+    InvalidStages(String),
+    UniformNotFound(String),
+    UniformTypeMismatch {
+        name: String,
+        declared: GLenum,
+        provided: GLenum,
+    },
+    UniformArrayLength {
+        name: String,
+        declared: usize,
+        provided: usize,
+    },
+    UniformBlockNotFound(String),
+    StorageBlockNotFound(String),
+    BlockSizeMismatch {
+        name: String,
+        block: usize,
+        rust: usize,
+    },
+    Preprocess {
+        file: PathBuf,
+        line: u32,
+        message: String,
+    },
+    IncludeCycle(Vec<PathBuf>),
+    Parse {
+        file: PathBuf,
+        line: u32,
+        message: String,
+    },
+    BufferOutOfRange {
+        offset: usize,
+        len: usize,
+        size: usize,
+    },
+    BufferMap,
+    ImmutableBuffer,
+    BufferNotIndexed,
+    MisalignedOffset {
+        offset: usize,
+        alignment: usize,
+    },
+    OverlappingCopy {
+        src_offset: usize,
+        dst_offset: usize,
+        len: usize,
+    },
+    BindingInUse(u32),
+    TextureDataSize {
+        expected: usize,
+        actual: usize,
+    },
+    ImageSize {
+        file: PathBuf,
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "i/o error: {err}"),
+            Self::Image(err) => write!(f, "image decoding error: {err}"),
+            Self::Gltf(err) => write!(f, "glTF error: {err}"),
+            Self::UnsupportedUvSet { material, uv_set } => write!(
+                f,
+                "material `{}` uses TEXCOORD_{uv_set}, only TEXCOORD_0 and TEXCOORD_1 are loaded",
+                material.as_deref().unwrap_or("<unnamed>")
+            ),
+            Self::Nul(err) => write!(f, "string passed to OpenGL contains a nul byte: {err}"),
+            Self::ShaderCompile {
+                stage,
+                log,
+                diagnostics,
+            } => {
+                write!(f, "failed to compile {stage:?} shader:")?;
+
+                if diagnostics.is_empty() {
+                    return write!(f, "\n{log}");
+                }
+
+                for diagnostic in diagnostics {
+                    write!(f, "\n{diagnostic}")?;
+                }
+
+                Ok(())
+            }
+            Self::ProgramLink { log } => write!(f, "failed to link program:\n{log}"),
+            Self::InvalidStages(message) => write!(f, "invalid shader stages: {message}"),
+            Self::UniformNotFound(name) => write!(f, "uniform `{name}` not found"),
+            Self::UniformTypeMismatch {
+                name,
+                declared,
+                provided,
+            } => {
+                let type_name = |t: &GLenum| {
+                    uniform::glsl_name(*t)
+                        .map(str::to_owned)
+                        .unwrap_or_else(|| format!("{t:#06x}"))
+                };
+
+                write!(
+                    f,
+                    "uniform `{name}` is declared as {} but was set as {}",
+                    type_name(declared),
+                    type_name(provided)
+                )
+            }
+            Self::UniformArrayLength {
+                name,
+                declared,
+                provided,
+            } => write!(
+                f,
+                "uniform `{name}` has room for {declared} element(s), {provided} given"
+            ),
+            Self::UniformBlockNotFound(name) => write!(f, "uniform block `{name}` not found"),
+            Self::StorageBlockNotFound(name) => {
+                write!(f, "shader storage block `{name}` not found")
+            }
+            Self::BlockSizeMismatch { name, block, rust } => write!(
+                f,
+                "block `{name}` is {block} bytes in GLSL but {rust} bytes in Rust"
+            ),
+            Self::Preprocess {
+                file,
+                line,
+                message,
+            }
+            | Self::Parse {
+                file,
+                line,
+                message,
+            } => write!(f, "{}:{line}: {message}", file.display()),
+            Self::IncludeCycle(cycle) => {
+                write!(f, "include cycle:")?;
+
+                for file in cycle {
+                    write!(f, "\n  {}", file.display())?;
+                }
+
+                Ok(())
+            }
+            Self::BufferOutOfRange { offset, len, size } => write!(
+                f,
+                "{len} bytes at offset {offset} are out of range of a {size} byte buffer"
+            ),
+            Self::BufferMap => write!(f, "failed to map buffer"),
+            Self::ImmutableBuffer => write!(f, "immutable buffer storage can't be reallocated"),
+            Self::BufferNotIndexed => write!(f, "buffer target has no indexed binding points"),
+            Self::MisalignedOffset { offset, alignment } => write!(
+                f,
+                "offset {offset} is not a multiple of the required alignment {alignment}"
+            ),
+            Self::OverlappingCopy {
+                src_offset,
+                dst_offset,
+                len,
+            } => write!(
+                f,
+                "copying {len} bytes from offset {src_offset} to {dst_offset} of the same buffer overlaps"
+            ),
+            Self::BindingInUse(binding) => {
+                write!(
+                    f,
+                    "binding point {binding} is already used by another buffer"
+                )
+            }
+            Self::TextureDataSize { expected, actual } => write!(
+                f,
+                "texture data is {actual} bytes but the texture needs {expected}"
+            ),
+            Self::ImageSize {
+                file,
+                expected: (expected_width, expected_height),
+                actual: (width, height),
+            } => write!(
+                f,
+                "{}: image is {width}x{height} but should be {expected_width}x{expected_height}",
+                file.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Image(err) => Some(err),
+            Self::Gltf(err) => Some(err),
+            Self::Nul(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ImageError> for Error {
+    fn from(err: ImageError) -> Self {
+        Self::Image(err)
+    }
+}
+
+impl From<GltfError> for Error {
+    fn from(err: GltfError) -> Self {
+        Self::Gltf(err)
+    }
+}
+
+impl From<NulError> for Error {
+    fn from(err: NulError) -> Self {
+        Self::Nul(err)
+    }
+}
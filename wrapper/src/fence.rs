@@ -0,0 +1,57 @@
+use gl::types::GLsync;
+
+/// A GPU fence, signaled once all GL commands issued before it have completed.
+pub struct Fence {
+    sync: GLsync,
+}
+
+impl Fence {
+    pub fn new() -> Self {
+        Self {
+            sync: unsafe { gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0) },
+        }
+    }
+
+    pub fn is_signaled(&self) -> bool {
+        let mut status = 0;
+
+        unsafe {
+            gl::GetSynciv(
+                self.sync,
+                gl::SYNC_STATUS,
+                1,
+                std::ptr::null_mut(),
+                &mut status,
+            );
+        }
+
+        status == gl::SIGNALED as i32
+    }
+
+    /// Blocks until the fence is signaled.
+    pub fn wait(&self) {
+        loop {
+            // the first wait flushes, so the fence is guaranteed to be reached
+            let result =
+                unsafe { gl::ClientWaitSync(self.sync, gl::SYNC_FLUSH_COMMANDS_BIT, 1_000_000) };
+
+            if result != gl::TIMEOUT_EXPIRED {
+                break;
+            }
+        }
+    }
+}
+
+impl Default for Fence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Fence {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteSync(self.sync);
+        }
+    }
+}
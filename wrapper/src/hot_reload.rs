@@ -0,0 +1,165 @@
+use std::{
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    error::Result,
+    shader_program::{Preprocessor, Shader, ShaderProgram, ShaderType},
+};
+
+/// A [`ShaderProgram`] built from files that can be rebuilt when they change.
+///
+/// Call [`ReloadableProgram::poll`] once per frame. If a changed source fails to
+/// compile or link, the previous program is kept and the error is returned.
+/// `#include`d files are watched too.
+pub struct ReloadableProgram {
+    stages: Vec<(PathBuf, ShaderType)>,
+    preprocessor: Preprocessor,
+    binary_cache: Option<PathBuf>,
+    watched: Vec<(PathBuf, Option<SystemTime>)>,
+    program: ShaderProgram,
+}
+
+pub struct Builder {
+    stages: Vec<(PathBuf, ShaderType)>,
+    preprocessor: Preprocessor,
+    binary_cache: Option<PathBuf>,
+}
+
+impl Builder {
+    pub fn attach(mut self, path: impl Into<PathBuf>, t: ShaderType) -> Self {
+        self.stages.push((path.into(), t));
+        self
+    }
+
+    pub fn define(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.preprocessor = self.preprocessor.define(name, value);
+        self
+    }
+
+    /// See [`shader_program::Builder::binary_cache`](crate::shader_program::Builder::binary_cache).
+    pub fn binary_cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.binary_cache = Some(dir.into());
+        self
+    }
+
+    pub fn link(self) -> Result<ReloadableProgram> {
+        let (program, files) = build(&self.stages, &self.preprocessor, &self.binary_cache)?;
+
+        Ok(ReloadableProgram {
+            stages: self.stages,
+            preprocessor: self.preprocessor,
+            binary_cache: self.binary_cache,
+            watched: watch(files),
+            program,
+        })
+    }
+}
+
+impl ReloadableProgram {
+    pub fn builder() -> Builder {
+        Builder {
+            stages: Vec::new(),
+            preprocessor: Preprocessor::new(),
+            binary_cache: None,
+        }
+    }
+
+    pub fn program(&self) -> &ShaderProgram {
+        &self.program
+    }
+
+    /// Checks the sources for changes and rebuilds the program if any changed.
+    ///
+    /// Returns `None` when nothing changed.
+    pub fn poll(&mut self) -> Option<Result<()>> {
+        let mut changed = false;
+
+        for (path, modified) in &mut self.watched {
+            // a missing file is usually an editor in the middle of saving it
+            let Some(current) = mtime(path) else {
+                continue;
+            };
+
+            if *modified != Some(current) {
+                *modified = Some(current);
+                changed = true;
+            }
+        }
+
+        changed.then(|| self.reload())
+    }
+
+    /// Rebuilds the program from the sources, keeping the old one on failure.
+    pub fn reload(&mut self) -> Result<()> {
+        let (program, files) = build(&self.stages, &self.preprocessor, &self.binary_cache)?;
+
+        // the old sources are gone, so their binary won't be asked for again
+        if let Some(old) = self.program.cache_file() {
+            if program.cache_file() != Some(old) {
+                let _ = fs::remove_file(old);
+            }
+        }
+
+        self.program = program;
+        self.watched = watch(files);
+
+        Ok(())
+    }
+}
+
+impl Deref for ReloadableProgram {
+    type Target = ShaderProgram;
+
+    fn deref(&self) -> &Self::Target {
+        &self.program
+    }
+}
+
+fn build(
+    stages: &[(PathBuf, ShaderType)],
+    preprocessor: &Preprocessor,
+    binary_cache: &Option<PathBuf>,
+) -> Result<(ShaderProgram, Vec<PathBuf>)> {
+    let shaders = stages
+        .iter()
+        .map(|(path, t)| Shader::from_preprocessed(preprocessor.process_file(path)?, *t))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut files: Vec<PathBuf> = shaders
+        .iter()
+        .flat_map(|shader| shader.files().iter().cloned())
+        .collect();
+    files.sort();
+    files.dedup();
+
+    let mut builder = ShaderProgram::builder();
+
+    if let Some(dir) = binary_cache {
+        builder = builder.binary_cache(dir);
+    }
+
+    let program = shaders
+        .into_iter()
+        .fold(builder, |builder, shader| builder.attach(shader))
+        .link()?;
+
+    Ok((program, files))
+}
+
+fn watch(files: Vec<PathBuf>) -> Vec<(PathBuf, Option<SystemTime>)> {
+    files
+        .into_iter()
+        .map(|path| {
+            let modified = mtime(&path);
+            (path, modified)
+        })
+        .collect()
+}
+
+fn mtime(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
@@ -0,0 +1,401 @@
+use nalgebra_glm as glm;
+
+pub use wrapper_derive::{Std140, Std430};
+
+/// A type with a GLSL `std140` memory layout, as used by uniform blocks.
+///
+/// The layout is computed at compile time from `ALIGN` and `SIZE`, so structs
+/// don't need manual padding: `#[derive(Std140)]` places every field at its
+/// `std140` offset when writing.
+///
+/// ```ignore
+/// #[derive(Std140)]
+/// struct Light {
+///     position: glm::Vec3,
+///     intensity: f32, // packed into the last component of `position`
+///     color: glm::Vec3,
+/// }
+/// ```
+pub trait Std140 {
+    const ALIGN: usize;
+    /// Size in bytes, including trailing padding.
+    const SIZE: usize;
+
+    /// Writes the value into `out`, which is exactly `SIZE` bytes long.
+    fn write_std140(&self, out: &mut [u8]);
+
+    fn std140_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; Self::SIZE];
+        self.write_std140(&mut bytes);
+        bytes
+    }
+}
+
+/// A type with a GLSL `std430` memory layout, as used by shader storage blocks.
+///
+/// Same as [`Std140`] except that arrays, matrix columns and structs are not
+/// rounded up to 16 bytes. Values can be read back, so results computed on the
+/// GPU can be checked on the CPU.
+pub trait Std430: Sized {
+    const ALIGN: usize;
+    /// Size in bytes, including trailing padding.
+    const SIZE: usize;
+    /// Distance between elements of an array of `Self`.
+    const STRIDE: usize = align_to(Self::SIZE, Self::ALIGN);
+
+    /// Writes the value into `out`, which is exactly `SIZE` bytes long.
+    fn write_std430(&self, out: &mut [u8]);
+
+    /// Reads a value from `bytes`, which is exactly `SIZE` bytes long.
+    fn read_std430(bytes: &[u8]) -> Self;
+}
+
+pub const fn align_to(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+#[doc(hidden)]
+pub const fn max_align(aligns: &[usize]) -> usize {
+    let mut max = 1;
+    let mut i = 0;
+
+    while i < aligns.len() {
+        if aligns[i] > max {
+            max = aligns[i];
+        }
+
+        i += 1;
+    }
+
+    max
+}
+
+// vec2 is aligned to twice its component, vec3 and vec4 to four times
+const fn vector_align(components: usize, scalar_align: usize) -> usize {
+    match components {
+        1 => scalar_align,
+        2 => 2 * scalar_align,
+        _ => 4 * scalar_align,
+    }
+}
+
+macro_rules! impl_scalar {
+    ($($t:ty),*) => {
+        $(
+            impl Std140 for $t {
+                const ALIGN: usize = std::mem::size_of::<$t>();
+                const SIZE: usize = std::mem::size_of::<$t>();
+
+                fn write_std140(&self, out: &mut [u8]) {
+                    out.copy_from_slice(&self.to_ne_bytes());
+                }
+            }
+
+            impl Std430 for $t {
+                const ALIGN: usize = std::mem::size_of::<$t>();
+                const SIZE: usize = std::mem::size_of::<$t>();
+
+                fn write_std430(&self, out: &mut [u8]) {
+                    out.copy_from_slice(&self.to_ne_bytes());
+                }
+
+                fn read_std430(bytes: &[u8]) -> Self {
+                    Self::from_ne_bytes(bytes.try_into().unwrap())
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar!(f32, i32, u32, f64);
+
+impl Std140 for bool {
+    const ALIGN: usize = 4;
+    const SIZE: usize = 4;
+
+    fn write_std140(&self, out: &mut [u8]) {
+        u32::from(*self).write_std140(out);
+    }
+}
+
+impl Std430 for bool {
+    const ALIGN: usize = 4;
+    const SIZE: usize = 4;
+
+    fn write_std430(&self, out: &mut [u8]) {
+        u32::from(*self).write_std430(out);
+    }
+
+    fn read_std430(bytes: &[u8]) -> Self {
+        u32::read_std430(bytes) != 0
+    }
+}
+
+/// Vectors (`C == 1`) and column-major matrices, stored as an array of `C`
+/// column vectors.
+impl<T: Std140 + glm::Scalar, const R: usize, const C: usize> Std140 for glm::TMat<T, R, C> {
+    const ALIGN: usize = match C {
+        1 => vector_align(R, T::ALIGN),
+        _ => align_to(vector_align(R, T::ALIGN), 16),
+    };
+    const SIZE: usize = match C {
+        1 => R * T::SIZE,
+        _ => C * align_to(R * T::SIZE, Self::ALIGN),
+    };
+
+    fn write_std140(&self, out: &mut [u8]) {
+        let stride = match C {
+            1 => R * T::SIZE,
+            _ => align_to(R * T::SIZE, Self::ALIGN),
+        };
+
+        for (c, column) in self.column_iter().enumerate() {
+            for (r, value) in column.iter().enumerate() {
+                let offset = c * stride + r * T::SIZE;
+                value.write_std140(&mut out[offset..offset + T::SIZE]);
+            }
+        }
+    }
+}
+
+/// Array elements are aligned to at least 16 bytes.
+impl<T: Std140, const N: usize> Std140 for [T; N] {
+    const ALIGN: usize = align_to(T::ALIGN, 16);
+    const SIZE: usize = N * align_to(T::SIZE, Self::ALIGN);
+
+    fn write_std140(&self, out: &mut [u8]) {
+        let stride = align_to(T::SIZE, Self::ALIGN);
+
+        for (i, value) in self.iter().enumerate() {
+            value.write_std140(&mut out[i * stride..i * stride + T::SIZE]);
+        }
+    }
+}
+
+impl<T: Std430 + glm::Scalar, const R: usize, const C: usize> Std430 for glm::TMat<T, R, C> {
+    const ALIGN: usize = vector_align(R, T::ALIGN);
+    const SIZE: usize = match C {
+        1 => R * T::SIZE,
+        _ => C * align_to(R * T::SIZE, Self::ALIGN),
+    };
+
+    fn write_std430(&self, out: &mut [u8]) {
+        let stride = align_to(R * T::SIZE, Self::ALIGN);
+
+        for (c, column) in self.column_iter().enumerate() {
+            for (r, value) in column.iter().enumerate() {
+                let offset = c * stride + r * T::SIZE;
+                value.write_std430(&mut out[offset..offset + T::SIZE]);
+            }
+        }
+    }
+
+    fn read_std430(bytes: &[u8]) -> Self {
+        let stride = align_to(R * T::SIZE, Self::ALIGN);
+
+        Self::from_fn(|r, c| {
+            let offset = c * stride + r * T::SIZE;
+            T::read_std430(&bytes[offset..offset + T::SIZE])
+        })
+    }
+}
+
+impl<T: Std430, const N: usize> Std430 for [T; N] {
+    const ALIGN: usize = T::ALIGN;
+    const SIZE: usize = N * T::STRIDE;
+
+    fn write_std430(&self, out: &mut [u8]) {
+        for (i, value) in self.iter().enumerate() {
+            value.write_std430(&mut out[i * T::STRIDE..i * T::STRIDE + T::SIZE]);
+        }
+    }
+
+    fn read_std430(bytes: &[u8]) -> Self {
+        std::array::from_fn(|i| T::read_std430(&bytes[i * T::STRIDE..i * T::STRIDE + T::SIZE]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+        f32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[derive(Std140, Std430, Debug, PartialEq)]
+    struct Light {
+        position: glm::Vec3,
+        intensity: f32,
+        color: glm::Vec3,
+    }
+
+    #[derive(Std140, Std430, Debug, PartialEq)]
+    struct Inner {
+        x: f32,
+    }
+
+    #[derive(Std140, Std430, Debug, PartialEq)]
+    struct Outer {
+        inner: Inner,
+        y: f32,
+    }
+
+    #[derive(Std140, Std430, Debug, PartialEq)]
+    struct Weights {
+        weights: [f32; 3],
+        bias: f32,
+    }
+
+    #[test]
+    fn vectors() {
+        assert_eq!(
+            (<glm::Vec2 as Std140>::ALIGN, <glm::Vec2 as Std140>::SIZE),
+            (8, 8)
+        );
+        assert_eq!(
+            (<glm::Vec3 as Std140>::ALIGN, <glm::Vec3 as Std140>::SIZE),
+            (16, 12)
+        );
+        assert_eq!(
+            (<glm::Vec4 as Std140>::ALIGN, <glm::Vec4 as Std140>::SIZE),
+            (16, 16)
+        );
+        assert_eq!(
+            (<glm::DVec3 as Std430>::ALIGN, <glm::DVec3 as Std430>::SIZE),
+            (32, 24)
+        );
+
+        // arrays of vec3 still step by a whole vec4
+        assert_eq!(<glm::Vec3 as Std430>::STRIDE, 16);
+    }
+
+    #[test]
+    fn vec3_padding() {
+        // `intensity` fills the last component of `position`
+        assert_eq!(
+            (<Light as Std140>::ALIGN, <Light as Std140>::SIZE),
+            (16, 32)
+        );
+        assert_eq!(
+            (<Light as Std430>::ALIGN, <Light as Std430>::SIZE),
+            (16, 32)
+        );
+
+        let light = Light {
+            position: glm::vec3(1., 2., 3.),
+            intensity: 4.,
+            color: glm::vec3(5., 6., 7.),
+        };
+        let bytes = light.std140_bytes();
+
+        assert_eq!(f32_at(&bytes, 8), 3.);
+        assert_eq!(f32_at(&bytes, 12), 4.);
+        assert_eq!(f32_at(&bytes, 16), 5.);
+        assert_eq!(f32_at(&bytes, 24), 7.);
+    }
+
+    #[test]
+    fn array_stride() {
+        assert_eq!(<[f32; 3] as Std140>::SIZE, 48);
+        assert_eq!(<[f32; 3] as Std430>::SIZE, 12);
+        assert_eq!(<[glm::Vec3; 2] as Std430>::SIZE, 32);
+
+        assert_eq!(
+            (<Weights as Std140>::ALIGN, <Weights as Std140>::SIZE),
+            (16, 64)
+        );
+        assert_eq!(
+            (<Weights as Std430>::ALIGN, <Weights as Std430>::SIZE),
+            (4, 16)
+        );
+
+        let weights = Weights {
+            weights: [1., 2., 3.],
+            bias: 4.,
+        };
+        let bytes = weights.std140_bytes();
+
+        assert_eq!(f32_at(&bytes, 16), 2.);
+        assert_eq!(f32_at(&bytes, 32), 3.);
+        assert_eq!(f32_at(&bytes, 48), 4.);
+
+        let mut bytes = vec![0; <Weights as Std430>::SIZE];
+        weights.write_std430(&mut bytes);
+
+        assert_eq!(f32_at(&bytes, 4), 2.);
+        assert_eq!(f32_at(&bytes, 12), 4.);
+    }
+
+    #[test]
+    fn matrix_columns() {
+        assert_eq!(
+            (<glm::Mat2 as Std140>::ALIGN, <glm::Mat2 as Std140>::SIZE),
+            (16, 32)
+        );
+        assert_eq!(
+            (<glm::Mat2 as Std430>::ALIGN, <glm::Mat2 as Std430>::SIZE),
+            (8, 16)
+        );
+        assert_eq!(
+            (<glm::Mat3 as Std140>::ALIGN, <glm::Mat3 as Std140>::SIZE),
+            (16, 48)
+        );
+        assert_eq!(
+            (<glm::Mat3 as Std430>::ALIGN, <glm::Mat3 as Std430>::SIZE),
+            (16, 48)
+        );
+        assert_eq!(<glm::Mat4 as Std140>::SIZE, 64);
+
+        // column-major, each column padded to a vec4
+        let m: glm::Mat3 = glm::mat3(1., 2., 3., 4., 5., 6., 7., 8., 9.);
+        let bytes = m.std140_bytes();
+
+        assert_eq!(f32_at(&bytes, 0), 1.);
+        assert_eq!(f32_at(&bytes, 4), 4.);
+        assert_eq!(f32_at(&bytes, 16), 2.);
+        assert_eq!(f32_at(&bytes, 40), 9.);
+
+        let m: glm::Mat2 = glm::mat2(1., 2., 3., 4.);
+        let mut bytes = vec![0; <glm::Mat2 as Std430>::SIZE];
+        m.write_std430(&mut bytes);
+
+        assert_eq!(f32_at(&bytes, 8), 2.);
+        assert_eq!(<glm::Mat2 as Std430>::read_std430(&bytes), m);
+    }
+
+    #[test]
+    fn struct_rounding() {
+        // std140 rounds struct alignment up to a vec4, std430 doesn't
+        assert_eq!(
+            (<Inner as Std140>::ALIGN, <Inner as Std140>::SIZE),
+            (16, 16)
+        );
+        assert_eq!(
+            (<Outer as Std140>::ALIGN, <Outer as Std140>::SIZE),
+            (16, 32)
+        );
+        assert_eq!((<Inner as Std430>::ALIGN, <Inner as Std430>::SIZE), (4, 4));
+        assert_eq!((<Outer as Std430>::ALIGN, <Outer as Std430>::SIZE), (4, 8));
+
+        let outer = Outer {
+            inner: Inner { x: 1. },
+            y: 2.,
+        };
+
+        assert_eq!(f32_at(&outer.std140_bytes(), 16), 2.);
+    }
+
+    #[test]
+    fn std430_round_trip() {
+        let light = Light {
+            position: glm::vec3(1., 2., 3.),
+            intensity: 4.,
+            color: glm::vec3(5., 6., 7.),
+        };
+        let mut bytes = vec![0; <Light as Std430>::SIZE];
+        light.write_std430(&mut bytes);
+
+        assert_eq!(Light::read_std430(&bytes), light);
+    }
+}
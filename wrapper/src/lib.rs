@@ -1,6 +1,27 @@
 pub mod buffer;
 pub mod camera;
-#[macro_use]
+pub mod diagnostic;
+pub mod error;
+pub mod fence;
+pub mod hot_reload;
+pub mod layout;
+pub mod material;
+pub mod mesh;
+pub mod obj;
+pub mod persistent_buffer;
+pub mod primitives;
+pub mod ring_buffer;
+pub mod sampler;
+pub mod scene;
 pub mod shader_program;
+pub mod storage_buffer;
 pub mod texture;
+pub mod texture_units;
+pub mod uniform;
+pub mod uniform_buffer;
 pub mod vertex_array;
+
+// lets derives refer to `::wrapper` inside this crate too
+extern crate self as wrapper;
+
+pub use error::{Error, Result};
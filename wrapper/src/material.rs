@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+
+use nalgebra_glm as glm;
+
+/// Surface properties of a loaded model, with the Phong parameters of MTL
+/// files. Texture paths are resolved relative to the file that named them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: String,
+    pub ambient: glm::Vec3,
+    pub diffuse: glm::Vec3,
+    pub specular: glm::Vec3,
+    pub shininess: f32,
+    /// 1 for fully opaque.
+    pub opacity: f32,
+    pub diffuse_texture: Option<PathBuf>,
+    pub specular_texture: Option<PathBuf>,
+    pub normal_texture: Option<PathBuf>,
+}
+
+/// How the alpha of a [`PbrMaterial`]'s base color is used.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlphaMode {
+    Opaque,
+    /// Fragments with an alpha below the cutoff are discarded.
+    Mask(f32),
+    Blend,
+}
+
+/// A glTF metallic-roughness material. Textures are indices into the
+/// textures of the [`Scene`](crate::scene::Scene) it belongs to, and are read
+/// with the UV set given next to them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PbrMaterial {
+    pub name: Option<String>,
+    pub base_color: glm::Vec4,
+    pub base_color_texture: Option<TextureRef>,
+    pub metallic: f32,
+    pub roughness: f32,
+    /// Metalness in the blue channel, roughness in the green one.
+    pub metallic_roughness_texture: Option<TextureRef>,
+    pub normal_texture: Option<TextureRef>,
+    pub normal_scale: f32,
+    pub occlusion_texture: Option<TextureRef>,
+    pub occlusion_strength: f32,
+    pub emissive: glm::Vec3,
+    pub emissive_texture: Option<TextureRef>,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureRef {
+    pub texture: usize,
+    /// 0 for [`MeshVertex::uv`](crate::mesh::MeshVertex::uv), 1 for
+    /// [`MeshVertex::uv1`](crate::mesh::MeshVertex::uv1).
+    pub uv_set: u32,
+}
+
+impl Default for PbrMaterial {
+    fn default() -> Self {
+        Self {
+            name: None,
+            base_color: glm::vec4(1., 1., 1., 1.),
+            base_color_texture: None,
+            metallic: 1.,
+            roughness: 1.,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.,
+            occlusion_texture: None,
+            occlusion_strength: 1.,
+            emissive: glm::Vec3::zeros(),
+            emissive_texture: None,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            ambient: glm::Vec3::zeros(),
+            diffuse: glm::vec3(0.8, 0.8, 0.8),
+            specular: glm::Vec3::zeros(),
+            shininess: 32.,
+            opacity: 1.,
+            diffuse_texture: None,
+            specular_texture: None,
+            normal_texture: None,
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use bytemuck::{Pod, Zeroable};
+use nalgebra_glm as glm;
+
+use crate::{
+    buffer::{Buffer, BufferType, DrawType},
+    error::{Error, Result},
+    vertex_array::{Primitive, Vertex, VertexArray},
+};
+
+/// Axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: glm::Vec3,
+    pub max: glm::Vec3,
+}
+
+impl Aabb {
+    /// Smallest box containing all `points`, `None` if there are none.
+    pub fn from_points(points: impl IntoIterator<Item = glm::Vec3>) -> Option<Self> {
+        points.into_iter().fold(None, |aabb, point| {
+            Some(match aabb {
+                None => Aabb {
+                    min: point,
+                    max: point,
+                },
+                Some(Aabb { min, max }) => Aabb {
+                    min: glm::min2(&min, &point),
+                    max: glm::max2(&max, &point),
+                },
+            })
+        })
+    }
+
+    pub fn center(&self) -> glm::Vec3 {
+        (self.min + self.max) / 2.
+    }
+
+    pub fn size(&self) -> glm::Vec3 {
+        self.max - self.min
+    }
+}
+
+/// Vertices, optional `u32` indices and the vertex array drawing them.
+///
+/// The vertices are kept on the CPU too, so they can be updated in place and
+/// the bounding box kept up to date.
+pub struct Mesh<V: Vertex> {
+    vertices: Vec<V>,
+    vbo: Buffer,
+    ebo: Option<Buffer>,
+    vao: VertexArray,
+    bounds: Option<Aabb>,
+}
+
+impl<V: Vertex> Mesh<V> {
+    pub fn new(vertices: Vec<V>, indices: Vec<u32>) -> Self {
+        Self::build(vertices, Some(indices))
+    }
+
+    /// A mesh drawn without indices.
+    pub fn from_vertices(vertices: Vec<V>) -> Self {
+        Self::build(vertices, None)
+    }
+
+    fn build(vertices: Vec<V>, indices: Option<Vec<u32>>) -> Self {
+        // new buffers have no immutable storage, so `data` can't fail
+        let mut vbo = Buffer::new(BufferType::Array);
+        vbo.data(&vertices, DrawType::StaticDraw).unwrap();
+
+        let ebo = indices.map(|indices| {
+            // binding the indices would replace those of the last drawn mesh
+            VertexArray::unbind();
+
+            let mut ebo = Buffer::new(BufferType::ElementArray);
+            ebo.data(&indices, DrawType::StaticDraw).unwrap();
+            ebo
+        });
+
+        let vao = VertexArray::with_layout::<V>(&vbo, ebo.as_ref());
+
+        Self {
+            bounds: bounds(&vertices),
+            vertices,
+            vbo,
+            ebo,
+            vao,
+        }
+    }
+
+    pub fn vertices(&self) -> &[V] {
+        &self.vertices
+    }
+
+    /// Overwrites the vertices from `first` on with `vertices`, which have to
+    /// fit into the mesh.
+    pub fn update_vertices(&mut self, first: usize, vertices: &[V]) -> Result<()> {
+        let size = std::mem::size_of::<V>();
+        let out_of_range = || Error::BufferOutOfRange {
+            offset: first.saturating_mul(size),
+            len: std::mem::size_of_val(vertices),
+            size: self.vbo.size(),
+        };
+
+        let end = first.checked_add(vertices.len()).ok_or_else(out_of_range)?;
+
+        let Some(target) = self.vertices.get_mut(first..end) else {
+            return Err(out_of_range());
+        };
+
+        target.copy_from_slice(vertices);
+        self.vbo.sub_data(first * size, vertices)?;
+        self.bounds = bounds(&self.vertices);
+
+        Ok(())
+    }
+
+    /// Bounding box of the vertex positions, `None` if the mesh is empty or
+    /// `V` has no position.
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.bounds
+    }
+
+    pub fn vertex_buffer(&self) -> &Buffer {
+        &self.vbo
+    }
+
+    pub fn index_buffer(&self) -> Option<&Buffer> {
+        self.ebo.as_ref()
+    }
+
+    pub fn vertex_array(&self) -> &VertexArray {
+        &self.vao
+    }
+
+    pub fn draw(&self, primitive: Primitive) {
+        match self.ebo {
+            Some(_) => self.vao.draw_indexed(primitive),
+            None => self.vao.draw(primitive),
+        }
+    }
+
+    pub fn draw_instanced(&self, primitive: Primitive, instances: u32) {
+        match self.ebo {
+            Some(_) => self.vao.draw_indexed_instanced(primitive, instances),
+            None => self.vao.draw_instanced(primitive, instances),
+        }
+    }
+}
+
+/// The vertex format of generated and loaded meshes.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, Vertex)]
+pub struct MeshVertex {
+    pub position: glm::Vec3,
+    pub normal: glm::Vec3,
+    pub uv: glm::Vec2,
+    /// Tangent along increasing `u`, with `w` the sign of the bitangent
+    /// (`cross(normal, tangent.xyz) * w` points along increasing `v`).
+    pub tangent: glm::Vec4,
+    /// Second UV set, e.g. for lightmaps. A copy of `uv` if there is none.
+    pub uv1: glm::Vec2,
+}
+
+/// Indexed triangles on the CPU, before they are uploaded as a [`Mesh`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeshData {
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a vertex without a tangent and returns its index.
+    pub fn push_vertex(&mut self, position: glm::Vec3, normal: glm::Vec3, uv: glm::Vec2) -> u32 {
+        self.vertices.push(MeshVertex {
+            position,
+            normal,
+            uv,
+            tangent: glm::Vec4::zeros(),
+            uv1: uv,
+        });
+
+        self.vertices.len() as u32 - 1
+    }
+
+    /// Adds a triangle, counter-clockwise when seen from the front.
+    pub fn push_triangle(&mut self, a: u32, b: u32, c: u32) {
+        self.indices.extend([a, b, c]);
+    }
+
+    /// Computes smooth normals, averaging the faces around each vertex
+    /// weighted by their area.
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![glm::Vec3::zeros(); self.vertices.len()];
+
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| self.vertices[triangle[i] as usize].position);
+            let normal = (b - a).cross(&(c - a));
+
+            for &i in triangle {
+                normals[i as usize] += normal;
+            }
+        }
+
+        for (vertex, normal) in self.vertices.iter_mut().zip(normals) {
+            vertex.normal = match normal.norm() > f32::EPSILON {
+                true => normal.normalize(),
+                false => glm::Vec3::y(),
+            };
+        }
+    }
+
+    /// Computes tangents from the UVs, orthogonal to the normals.
+    pub fn compute_tangents(&mut self) {
+        let mut tangents = vec![glm::Vec3::zeros(); self.vertices.len()];
+        let mut bitangents = vec![glm::Vec3::zeros(); self.vertices.len()];
+
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| &self.vertices[triangle[i] as usize]);
+
+            let (e1, e2) = (b.position - a.position, c.position - a.position);
+            let (d1, d2) = (b.uv - a.uv, c.uv - a.uv);
+
+            let det = d1.x * d2.y - d2.x * d1.y;
+
+            // no usable UVs on this triangle
+            if det.abs() < f32::EPSILON {
+                continue;
+            }
+
+            let tangent = (e1 * d2.y - e2 * d1.y) / det;
+            let bitangent = (e2 * d1.x - e1 * d2.x) / det;
+
+            for &i in triangle {
+                tangents[i as usize] += tangent;
+                bitangents[i as usize] += bitangent;
+            }
+        }
+
+        for ((vertex, tangent), bitangent) in self.vertices.iter_mut().zip(tangents).zip(bitangents)
+        {
+            let normal = vertex.normal;
+            let mut tangent = tangent - normal * normal.dot(&tangent);
+
+            if tangent.norm() < 1e-6 {
+                tangent = any_perpendicular(&normal);
+            }
+
+            let tangent = tangent.normalize();
+            let w = match normal.cross(&tangent).dot(&bitangent) < 0. {
+                true => -1.,
+                false => 1.,
+            };
+
+            vertex.tangent = glm::vec4(tangent.x, tangent.y, tangent.z, w);
+        }
+    }
+
+    pub fn into_mesh(self) -> Mesh<MeshVertex> {
+        Mesh::new(self.vertices, self.indices)
+    }
+}
+
+fn any_perpendicular(v: &glm::Vec3) -> glm::Vec3 {
+    let axis = match v.x.abs() < 0.9 {
+        true => glm::Vec3::x(),
+        false => glm::Vec3::y(),
+    };
+
+    v.cross(&axis)
+}
+
+fn bounds<V: Vertex>(vertices: &[V]) -> Option<Aabb> {
+    Aabb::from_points(vertices.iter().map_while(Vertex::position))
+}
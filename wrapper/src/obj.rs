@@ -0,0 +1,502 @@
+//! Wavefront OBJ models with their MTL materials.
+//!
+//! Supports positions, UVs, normals, polygonal faces (triangulated as fans, so
+//! they should be convex), objects, groups and materials. Smoothing groups are
+//! ignored: missing normals are generated smooth across shared positions.
+//! Missing MTL files and unknown material names fall back to the default
+//! material, as exporters often write them.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use nalgebra_glm as glm;
+
+use crate::{
+    error::{Error, Result},
+    material::Material,
+    mesh::MeshData,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct ObjModel {
+    pub meshes: Vec<ObjMesh>,
+    pub materials: Vec<Material>,
+}
+
+/// Faces sharing an object or group name and a material.
+#[derive(Debug, Clone)]
+pub struct ObjMesh {
+    pub name: String,
+    pub data: MeshData,
+    /// Index into [`ObjModel::materials`], `None` for the default material.
+    pub material: Option<usize>,
+}
+
+/// Loads an OBJ file and the MTL files it references.
+pub fn load(path: impl AsRef<Path>) -> Result<ObjModel> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)?;
+
+    Parser::new(path).parse(&source)
+}
+
+/// Parses OBJ source. MTL files are looked up relative to `path`, which is
+/// also used in error messages.
+pub fn parse(source: &str, path: impl AsRef<Path>) -> Result<ObjModel> {
+    Parser::new(path.as_ref()).parse(source)
+}
+
+/// Loads all materials of an MTL file.
+pub fn load_materials(path: impl AsRef<Path>) -> Result<Vec<Material>> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)?;
+
+    parse_materials(&source, path)
+}
+
+// (position, uv, normal) indices of a face corner
+type Corner = (usize, Option<usize>, Option<usize>);
+
+struct Parser<'a> {
+    path: &'a Path,
+    line: u32,
+    positions: Vec<glm::Vec3>,
+    uvs: Vec<glm::Vec2>,
+    normals: Vec<glm::Vec3>,
+    model: ObjModel,
+    current: MeshBuilder,
+}
+
+#[derive(Default)]
+struct MeshBuilder {
+    name: String,
+    material: Option<usize>,
+    data: MeshData,
+    vertices: HashMap<Corner, u32>,
+    // position index of every vertex, and whether it came without a normal
+    positions: Vec<usize>,
+    missing_normals: Vec<bool>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(path: &'a Path) -> Self {
+        Self {
+            path,
+            line: 0,
+            positions: Vec::new(),
+            uvs: Vec::new(),
+            normals: Vec::new(),
+            model: ObjModel::default(),
+            current: MeshBuilder::default(),
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> Error {
+        Error::Parse {
+            file: self.path.to_path_buf(),
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn numbers(&self, args: &[&str], min: usize, keyword: &str) -> Result<Vec<f32>> {
+        numbers(args, min, keyword).map_err(|message| self.error(message))
+    }
+
+    fn parse(mut self, source: &str) -> Result<ObjModel> {
+        for (number, line) in source.lines().enumerate() {
+            self.line = number as u32 + 1;
+
+            let line = line.split('#').next().unwrap_or("");
+            let mut args = line.split_whitespace();
+
+            let Some(keyword) = args.next() else {
+                continue;
+            };
+
+            let args: Vec<&str> = args.collect();
+
+            match keyword {
+                "v" => {
+                    let p = self.numbers(&args, 3, keyword)?;
+                    self.positions.push(glm::vec3(p[0], p[1], p[2]));
+                }
+                "vt" => {
+                    let uv = self.numbers(&args, 1, keyword)?;
+                    self.uvs
+                        .push(glm::vec2(uv[0], uv.get(1).copied().unwrap_or(0.)));
+                }
+                "vn" => {
+                    let n = self.numbers(&args, 3, keyword)?;
+                    self.normals.push(glm::vec3(n[0], n[1], n[2]));
+                }
+                "f" => self.face(&args)?,
+                "o" | "g" => {
+                    self.finish_mesh();
+                    self.current.name = args.join(" ");
+                }
+                "usemtl" => {
+                    let name = args.join(" ");
+                    let material = self.model.materials.iter().position(|m| m.name == name);
+
+                    if material != self.current.material {
+                        self.finish_mesh();
+                        self.current.material = material;
+                    }
+                }
+                "mtllib" => {
+                    if args.is_empty() {
+                        return Err(self.error("`mtllib` needs a file name"));
+                    }
+
+                    // every argument is a separate file
+                    let dir = self.path.parent().unwrap_or(Path::new(""));
+
+                    for file in &args {
+                        match load_materials(dir.join(file)) {
+                            Ok(materials) => self.model.materials.extend(materials),
+                            Err(Error::Io(err)) if err.kind() == io::ErrorKind::NotFound => {}
+                            Err(err) => return Err(err),
+                        }
+                    }
+                }
+                // smoothing groups, lines, points, curves, ...
+                _ => {}
+            }
+        }
+
+        self.finish_mesh();
+
+        Ok(self.model)
+    }
+
+    fn face(&mut self, args: &[&str]) -> Result<()> {
+        if args.len() < 3 {
+            return Err(self.error("a face needs at least 3 vertices"));
+        }
+
+        let corners = args
+            .iter()
+            .map(|corner| self.corner(corner))
+            .collect::<Result<Vec<_>>>()?;
+
+        let indices: Vec<u32> = corners.into_iter().map(|c| self.vertex(c)).collect();
+
+        for i in 1..indices.len() - 1 {
+            self.current
+                .data
+                .push_triangle(indices[0], indices[i], indices[i + 1]);
+        }
+
+        Ok(())
+    }
+
+    /// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+    fn corner(&self, corner: &str) -> Result<Corner> {
+        let mut parts = corner.split('/');
+
+        let mut index = |len: usize, required: bool, what: &str| -> Result<Option<usize>> {
+            match parts.next() {
+                None | Some("") if !required => Ok(None),
+                None | Some("") => Err(self.error(format!("missing {what} index in `{corner}`"))),
+                Some(index) => {
+                    let index: i64 = index
+                        .parse()
+                        .map_err(|_| self.error(format!("invalid {what} index `{index}`")))?;
+
+                    // 1-based, or counted back from the last element if negative
+                    let resolved = match index {
+                        i if i > 0 => i - 1,
+                        i => len as i64 + i,
+                    };
+
+                    match index != 0 && (0..len as i64).contains(&resolved) {
+                        true => Ok(Some(resolved as usize)),
+                        false => Err(self.error(format!("{what} index {index} is out of range"))),
+                    }
+                }
+            }
+        };
+
+        let position = index(self.positions.len(), true, "position")?.unwrap();
+        let uv = index(self.uvs.len(), false, "texture coordinate")?;
+        let normal = index(self.normals.len(), false, "normal")?;
+
+        Ok((position, uv, normal))
+    }
+
+    fn vertex(&mut self, corner: Corner) -> u32 {
+        let mesh = &mut self.current;
+
+        if let Some(&index) = mesh.vertices.get(&corner) {
+            return index;
+        }
+
+        let (position, uv, normal) = corner;
+
+        let index = mesh.data.push_vertex(
+            self.positions[position],
+            normal.map_or(glm::Vec3::zeros(), |n| self.normals[n].normalize()),
+            uv.map_or(glm::Vec2::zeros(), |uv| self.uvs[uv]),
+        );
+
+        mesh.vertices.insert(corner, index);
+        mesh.positions.push(position);
+        mesh.missing_normals.push(normal.is_none());
+
+        index
+    }
+
+    fn finish_mesh(&mut self) {
+        let mut mesh = std::mem::take(&mut self.current);
+
+        // the next mesh keeps the name and material until they change
+        self.current.name = mesh.name.clone();
+        self.current.material = mesh.material;
+
+        if mesh.data.indices.is_empty() {
+            return;
+        }
+
+        mesh.generate_normals();
+        mesh.data.compute_tangents();
+
+        self.model.meshes.push(ObjMesh {
+            name: mesh.name,
+            data: mesh.data,
+            material: mesh.material,
+        });
+    }
+}
+
+impl MeshBuilder {
+    /// Area weighted face normals, summed over all vertices sharing a position.
+    fn generate_normals(&mut self) {
+        if !self.missing_normals.contains(&true) {
+            return;
+        }
+
+        let mut normals: HashMap<usize, glm::Vec3> = HashMap::new();
+        let vertices = &self.data.vertices;
+
+        for triangle in self.data.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize].position);
+            let normal = (b - a).cross(&(c - a));
+
+            for &i in triangle {
+                *normals
+                    .entry(self.positions[i as usize])
+                    .or_insert(glm::Vec3::zeros()) += normal;
+            }
+        }
+
+        for (i, vertex) in self.data.vertices.iter_mut().enumerate() {
+            if !self.missing_normals[i] {
+                continue;
+            }
+
+            let normal = normals[&self.positions[i]];
+
+            vertex.normal = match normal.norm() > f32::EPSILON {
+                true => normal.normalize(),
+                false => glm::Vec3::y(),
+            };
+        }
+    }
+}
+
+/// Parses MTL source, resolving texture paths relative to `path`.
+pub fn parse_materials(source: &str, path: impl AsRef<Path>) -> Result<Vec<Material>> {
+    let path = path.as_ref();
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    let mut materials: Vec<Material> = Vec::new();
+
+    for (number, line) in source.lines().enumerate() {
+        let error = |message: String| Error::Parse {
+            file: path.to_path_buf(),
+            line: number as u32 + 1,
+            message,
+        };
+
+        let line = line.split('#').next().unwrap_or("");
+        let mut args = line.split_whitespace();
+
+        let Some(keyword) = args.next() else {
+            continue;
+        };
+
+        let args: Vec<&str> = args.collect();
+
+        if keyword == "newmtl" {
+            materials.push(Material {
+                name: args.join(" "),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let Some(material) = materials.last_mut() else {
+            return Err(error(format!("`{keyword}` before the first `newmtl`")));
+        };
+
+        let color = || -> Result<glm::Vec3> {
+            let c = numbers(&args, 1, keyword).map_err(error)?;
+
+            // a single value is used for all channels
+            Ok(match c.len() {
+                1 | 2 => glm::vec3(c[0], c[0], c[0]),
+                _ => glm::vec3(c[0], c[1], c[2]),
+            })
+        };
+        let number = || -> Result<f32> { Ok(numbers(&args, 1, keyword).map_err(error)?[0]) };
+
+        // texture options like `-bm 0.5` come before the file name
+        let texture = || -> Result<PathBuf> {
+            match args.last() {
+                Some(file) => Ok(dir.join(file)),
+                None => Err(error(format!("`{keyword}` needs a file name"))),
+            }
+        };
+
+        match keyword {
+            "Ka" => material.ambient = color()?,
+            "Kd" => material.diffuse = color()?,
+            "Ks" => material.specular = color()?,
+            "Ns" => material.shininess = number()?,
+            "d" => material.opacity = number()?,
+            "Tr" => material.opacity = 1. - number()?,
+            "map_Kd" => material.diffuse_texture = Some(texture()?),
+            "map_Ks" => material.specular_texture = Some(texture()?),
+            "map_Bump" | "map_bump" | "bump" | "norm" => material.normal_texture = Some(texture()?),
+            _ => {}
+        }
+    }
+
+    Ok(materials)
+}
+
+/// At least `min` numbers, as many as there are.
+fn numbers(args: &[&str], min: usize, keyword: &str) -> std::result::Result<Vec<f32>, String> {
+    if args.len() < min {
+        return Err(format!("`{keyword}` needs at least {min} values"));
+    }
+
+    args.iter()
+        .map(|arg| f32::from_str(arg).map_err(|_| format!("invalid number `{arg}`")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_one(source: &str) -> MeshData {
+        let mut model = parse(source, "model.obj").unwrap();
+        assert_eq!(model.meshes.len(), 1);
+
+        model.meshes.remove(0).data
+    }
+
+    fn parse_error(source: &str) -> String {
+        parse(source, "model.obj").unwrap_err().to_string()
+    }
+
+    const QUAD: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    #[test]
+    fn polygons_are_triangulated_as_fans() {
+        let data = parse_one(&format!("{QUAD}v 0.5 1.5 0\nf 1 2 3 4 5\n"));
+
+        assert_eq!(data.vertices.len(), 5);
+        assert_eq!(data.indices, [0, 1, 2, 0, 2, 3, 0, 3, 4]);
+    }
+
+    #[test]
+    fn negative_indices_count_back() {
+        let data = parse_one(&format!("{QUAD}f -4 -3 -2\n"));
+
+        let positions: Vec<_> = data.vertices.iter().map(|v| v.position).collect();
+        assert_eq!(
+            positions,
+            [
+                glm::vec3(0., 0., 0.),
+                glm::vec3(1., 0., 0.),
+                glm::vec3(1., 1., 0.)
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_corners_are_deduplicated() {
+        let data = parse_one(&format!(
+            "{QUAD}vt 0 0\nvt 1 1\nf 1/1 2/1 3/1\nf 1/1 3/1 4/1\nf 1/2 3/1 4/1\n"
+        ));
+
+        // the last face uses position 1 with another uv, so it gets its own vertex
+        assert_eq!(data.vertices.len(), 5);
+        assert_eq!(data.indices, [0, 1, 2, 0, 2, 3, 4, 2, 3]);
+    }
+
+    #[test]
+    fn missing_normals_are_generated() {
+        let data = parse_one(&format!("{QUAD}vn 1 0 0\nf 1 2 3\nf 1//1 3//1 4//1\n"));
+
+        // vertex 0 has no normal, the face normal of 1 2 3 points along +z
+        assert_eq!(data.vertices[0].normal, glm::vec3(0., 0., 1.));
+        // normals given in the file are kept
+        assert_eq!(data.vertices[3].normal, glm::vec3(1., 0., 0.));
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        assert_eq!(
+            parse_error("v 0 0 0\nv 1 x 0\n"),
+            "model.obj:2: invalid number `x`"
+        );
+        assert_eq!(
+            parse_error(&format!("{QUAD}\nf 1 2 5\n")),
+            "model.obj:6: position index 5 is out of range"
+        );
+        assert_eq!(
+            parse_error(&format!("{QUAD}f 1 2\n")),
+            "model.obj:5: a face needs at least 3 vertices"
+        );
+    }
+
+    #[test]
+    fn unknown_materials_fall_back_to_the_default() {
+        let model = parse(
+            &format!("mtllib missing.mtl\n{QUAD}usemtl brick\nf 1 2 3\n"),
+            "model.obj",
+        )
+        .unwrap();
+
+        assert!(model.materials.is_empty());
+        assert_eq!(model.meshes[0].material, None);
+    }
+
+    #[test]
+    fn mtllib_loads_every_file() {
+        let dir = std::env::temp_dir().join(format!("wrapper-obj-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.mtl"), "newmtl red\nKd 1 0 0\n").unwrap();
+        fs::write(dir.join("b.mtl"), "newmtl green\nKd 0 1 0\n").unwrap();
+
+        let model = parse(
+            &format!("mtllib a.mtl b.mtl\n{QUAD}usemtl green\nf 1 2 3\n"),
+            dir.join("model.obj"),
+        )
+        .unwrap();
+
+        let names: Vec<_> = model.materials.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["red", "green"]);
+        assert_eq!(model.meshes[0].material, Some(1));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    mem,
+    ops::{Deref, DerefMut},
+    slice,
+};
+
+use bytemuck::Pod;
+
+use crate::{
+    buffer::{Buffer, BufferType, MapAccess, StorageFlag},
+    error::{Error, Result},
+};
+
+/// Immutable buffer of `len` elements that stays mapped for its whole
+/// lifetime, so it can be written without map and unmap calls.
+///
+/// The mapping is coherent: writes become visible to GL without a barrier, but
+/// nothing stops them from landing while GL is still reading. Use a
+/// [`RingBuffer`](crate::ring_buffer::RingBuffer) for per-frame data.
+///
+/// The storage starts zeroed and is mapped for reading too, so it can be
+/// handed out as a slice; reads may still be slow.
+pub struct PersistentBuffer<T: Pod> {
+    buffer: Buffer,
+    ptr: *mut T,
+    len: usize,
+}
+
+impl<T: Pod> PersistentBuffer<T> {
+    pub fn new(t: BufferType, len: usize) -> Result<Self> {
+        let mut buffer = Buffer::new(t);
+        let bytes = len
+            .checked_mul(mem::size_of::<T>())
+            .ok_or(Error::BufferOutOfRange {
+                offset: 0,
+                len: usize::MAX,
+                size: 0,
+            })?;
+
+        buffer.storage(
+            &vec![0u8; bytes],
+            &[
+                StorageFlag::MapRead,
+                StorageFlag::MapWrite,
+                StorageFlag::MapPersistent,
+                StorageFlag::MapCoherent,
+            ],
+        )?;
+
+        let ptr = buffer.map_bytes(
+            0,
+            bytes,
+            &[
+                MapAccess::Read,
+                MapAccess::Write,
+                MapAccess::Persistent,
+                MapAccess::Coherent,
+            ],
+        )?;
+
+        Ok(Self {
+            buffer,
+            ptr: ptr as *mut T,
+            len,
+        })
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<T: Pod> Deref for PersistentBuffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<T: Pod> DerefMut for PersistentBuffer<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl<T: Pod> Drop for PersistentBuffer<T> {
+    fn drop(&mut self) {
+        self.buffer.unmap();
+    }
+}
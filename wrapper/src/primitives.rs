@@ -0,0 +1,503 @@
+//! Generators for common shapes, centered on the origin with Y up.
+//!
+//! Every shape is returned as indexed triangles with normals, UVs and
+//! tangents, wound counter-clockwise when seen from outside. Call
+//! [`MeshData::into_mesh`] to upload one.
+
+use std::{
+    collections::HashMap,
+    f32::consts::{FRAC_PI_2, PI, TAU},
+};
+
+use nalgebra_glm as glm;
+
+use crate::mesh::MeshData;
+
+/// A cube with sides of length `size`, with separate vertices per face so the
+/// edges stay sharp.
+pub fn cube(size: f32) -> MeshData {
+    let mut data = MeshData::new();
+    let h = size / 2.;
+
+    // (normal, u axis, v axis) with u x v = normal
+    let faces = [
+        (glm::Vec3::x(), -glm::Vec3::z(), glm::Vec3::y()),
+        (-glm::Vec3::x(), glm::Vec3::z(), glm::Vec3::y()),
+        (glm::Vec3::y(), glm::Vec3::x(), -glm::Vec3::z()),
+        (-glm::Vec3::y(), glm::Vec3::x(), glm::Vec3::z()),
+        (glm::Vec3::z(), glm::Vec3::x(), glm::Vec3::y()),
+        (-glm::Vec3::z(), -glm::Vec3::x(), glm::Vec3::y()),
+    ];
+
+    for (normal, u, v) in faces {
+        let corners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)].map(|(x, y): (f32, f32)| {
+            let position = (normal + u * (x * 2. - 1.) + v * (y * 2. - 1.)) * h;
+            data.push_vertex(position, normal, glm::vec2(x, y))
+        });
+
+        data.push_triangle(corners[0], corners[1], corners[2]);
+        data.push_triangle(corners[0], corners[2], corners[3]);
+    }
+
+    data.compute_tangents();
+    data
+}
+
+/// A sphere made of `segments` slices around the Y axis and `rings` stacks
+/// from pole to pole.
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> MeshData {
+    let rings = rings.max(2);
+
+    let rows: Vec<_> = (0..=rings)
+        .map(|r| {
+            let t = r as f32 / rings as f32;
+            (t * PI, 0., 1. - t)
+        })
+        .collect();
+
+    let mut data = MeshData::new();
+    revolve(&mut data, &rows, radius, segments);
+    data.compute_tangents();
+    data
+}
+
+/// A sphere made by splitting each triangle of an icosahedron into four
+/// `subdivisions` times, giving evenly sized triangles.
+pub fn icosphere(radius: f32, subdivisions: u32) -> MeshData {
+    let t = (1. + 5f32.sqrt()) / 2.;
+
+    let mut positions: Vec<glm::Vec3> = [
+        (-1., t, 0.),
+        (1., t, 0.),
+        (-1., -t, 0.),
+        (1., -t, 0.),
+        (0., -1., t),
+        (0., 1., t),
+        (0., -1., -t),
+        (0., 1., -t),
+        (t, 0., -1.),
+        (t, 0., 1.),
+        (-t, 0., -1.),
+        (-t, 0., 1.),
+    ]
+    .into_iter()
+    .map(|(x, y, z)| glm::vec3(x, y, z).normalize())
+    .collect();
+
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        let mut midpoints = HashMap::new();
+
+        let mut midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let p = (positions[a as usize] + positions[b as usize]).normalize();
+                positions.push(p);
+                positions.len() as u32 - 1
+            })
+        };
+
+        triangles = triangles
+            .into_iter()
+            .flat_map(|[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    // vertices are split where a triangle crosses the UV seam or touches a
+    // pole, so each copy gets a u that fits the triangle
+    let mut data = MeshData::new();
+    let mut copies = HashMap::new();
+
+    for triangle in triangles {
+        let mut us = triangle.map(|i| sphere_u(&positions[i as usize]));
+
+        let (min, max) = us.iter().fold((f32::MAX, f32::MIN), |(min, max), &u| {
+            (min.min(u), max.max(u))
+        });
+
+        if max - min > 0.5 {
+            for u in &mut us {
+                if *u < 0.5 {
+                    *u += 1.;
+                }
+            }
+        }
+
+        for i in 0..3 {
+            let p = positions[triangle[i] as usize];
+
+            if p.x.abs() < 1e-6 && p.z.abs() < 1e-6 {
+                us[i] = (us[(i + 1) % 3] + us[(i + 2) % 3]) / 2.;
+            }
+        }
+
+        let [a, b, c] = [0, 1, 2].map(|i| {
+            let p = positions[triangle[i] as usize];
+            let uv = glm::vec2(us[i], 0.5 + p.y.clamp(-1., 1.).asin() / PI);
+
+            *copies
+                .entry((triangle[i], us[i].to_bits()))
+                .or_insert_with(|| data.push_vertex(p * radius, p, uv))
+        });
+
+        data.push_triangle(a, b, c);
+    }
+
+    data.compute_tangents();
+    data
+}
+
+/// A `width` by `depth` plane facing up, split into `subdivisions + 1` cells
+/// along each side.
+pub fn plane(width: f32, depth: f32, subdivisions: u32) -> MeshData {
+    let cells = subdivisions + 1;
+    let mut data = MeshData::new();
+
+    for j in 0..=cells {
+        for i in 0..=cells {
+            let (u, v) = (i as f32 / cells as f32, j as f32 / cells as f32);
+            let position = glm::vec3((u - 0.5) * width, 0., (0.5 - v) * depth);
+
+            data.push_vertex(position, glm::Vec3::y(), glm::vec2(u, v));
+        }
+    }
+
+    let index = |i: u32, j: u32| j * (cells + 1) + i;
+
+    for j in 0..cells {
+        for i in 0..cells {
+            let (a, b) = (index(i, j), index(i + 1, j));
+            let (c, d) = (index(i + 1, j + 1), index(i, j + 1));
+
+            data.push_triangle(a, b, c);
+            data.push_triangle(a, c, d);
+        }
+    }
+
+    data.compute_tangents();
+    data
+}
+
+/// A closed cylinder along the Y axis.
+pub fn cylinder(radius: f32, height: f32, segments: u32) -> MeshData {
+    let h = height / 2.;
+    let mut data = MeshData::new();
+
+    revolve(
+        &mut data,
+        &[(FRAC_PI_2, h, 1.), (FRAC_PI_2, -h, 0.)],
+        radius,
+        segments,
+    );
+    disk(&mut data, h, radius, segments, true);
+    disk(&mut data, -h, radius, segments, false);
+
+    data.compute_tangents();
+    data
+}
+
+/// A closed cone along the Y axis with its tip at the top.
+pub fn cone(radius: f32, height: f32, segments: u32) -> MeshData {
+    let segments = segments.max(3);
+    let h = height / 2.;
+    let mut data = MeshData::new();
+
+    let normal = |phi: f32| glm::vec3(height * phi.sin(), radius, height * phi.cos()).normalize();
+
+    let rim: Vec<u32> = (0..=segments)
+        .map(|s| {
+            let phi = angle(s, segments);
+            let position = glm::vec3(radius * phi.sin(), -h, radius * phi.cos());
+
+            data.push_vertex(
+                position,
+                normal(phi),
+                glm::vec2(s as f32 / segments as f32, 0.),
+            )
+        })
+        .collect();
+
+    // the tip gets one vertex per segment, with the normal of that segment
+    for s in 0..segments {
+        let phi = angle(s, segments) + PI / segments as f32;
+        let uv = glm::vec2((s as f32 + 0.5) / segments as f32, 1.);
+        let tip = data.push_vertex(glm::vec3(0., h, 0.), normal(phi), uv);
+
+        data.push_triangle(rim[s as usize], rim[s as usize + 1], tip);
+    }
+
+    disk(&mut data, -h, radius, segments, false);
+
+    data.compute_tangents();
+    data
+}
+
+/// A torus around the Y axis. `major_radius` is the distance from the center
+/// to the middle of the tube, `minor_radius` the radius of the tube.
+pub fn torus(
+    major_radius: f32,
+    minor_radius: f32,
+    major_segments: u32,
+    minor_segments: u32,
+) -> MeshData {
+    let (major_segments, minor_segments) = (major_segments.max(3), minor_segments.max(3));
+    let mut data = MeshData::new();
+
+    for j in 0..=minor_segments {
+        for i in 0..=major_segments {
+            let (phi, theta) = (angle(i, major_segments), angle(j, minor_segments));
+
+            let center = glm::vec3(phi.sin(), 0., phi.cos()) * major_radius;
+            let normal = glm::vec3(
+                theta.cos() * phi.sin(),
+                theta.sin(),
+                theta.cos() * phi.cos(),
+            );
+            let uv = glm::vec2(
+                i as f32 / major_segments as f32,
+                j as f32 / minor_segments as f32,
+            );
+
+            data.push_vertex(center + normal * minor_radius, normal, uv);
+        }
+    }
+
+    let index = |i: u32, j: u32| j * (major_segments + 1) + i;
+
+    for j in 0..minor_segments {
+        for i in 0..major_segments {
+            let (a, b) = (index(i, j), index(i + 1, j));
+            let (c, d) = (index(i + 1, j + 1), index(i, j + 1));
+
+            data.push_triangle(a, b, c);
+            data.push_triangle(a, c, d);
+        }
+    }
+
+    data.compute_tangents();
+    data
+}
+
+/// A cylinder of `height` capped by two hemispheres, so the total height is
+/// `height + 2 * radius`. `rings` is the number of stacks per hemisphere.
+pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> MeshData {
+    let rings = rings.max(1);
+    let h = height / 2.;
+
+    // v runs along the profile, proportional to its length
+    let length = PI * radius + height;
+    let quarter = FRAC_PI_2 * radius;
+
+    let top = (0..=rings).map(|r| {
+        let t = r as f32 / rings as f32;
+        (t * FRAC_PI_2, h, 1. - t * quarter / length)
+    });
+    let bottom = (0..=rings).map(|r| {
+        let t = r as f32 / rings as f32;
+        (FRAC_PI_2 + t * FRAC_PI_2, -h, (1. - t) * quarter / length)
+    });
+
+    let rows: Vec<_> = top.chain(bottom).collect();
+
+    let mut data = MeshData::new();
+    revolve(&mut data, &rows, radius, segments);
+    data.compute_tangents();
+    data
+}
+
+fn angle(step: u32, steps: u32) -> f32 {
+    TAU * step as f32 / steps as f32
+}
+
+/// u of a point on the unit sphere, matching [`uv_sphere`].
+fn sphere_u(p: &glm::Vec3) -> f32 {
+    (p.x.atan2(p.z) / TAU).rem_euclid(1.)
+}
+
+/// Sweeps a profile around the Y axis. Each row is `(theta, y, v)`: the angle
+/// of the normal from +Y, the height of the row's center and its v
+/// coordinate. Rows go from top to bottom.
+fn revolve(data: &mut MeshData, rows: &[(f32, f32, f32)], radius: f32, segments: u32) {
+    let segments = segments.max(3);
+    let first = data.vertices.len() as u32;
+
+    for &(theta, y, v) in rows {
+        for s in 0..=segments {
+            let phi = angle(s, segments);
+            let normal = glm::vec3(
+                theta.sin() * phi.sin(),
+                theta.cos(),
+                theta.sin() * phi.cos(),
+            );
+            let uv = glm::vec2(s as f32 / segments as f32, v);
+
+            data.push_vertex(normal * radius + glm::vec3(0., y, 0.), normal, uv);
+        }
+    }
+
+    let index = |s: u32, row: usize| first + row as u32 * (segments + 1) + s;
+    let is_pole = |row: usize| rows[row].0.sin().abs() < 1e-6;
+
+    for row in 0..rows.len() - 1 {
+        for s in 0..segments {
+            let (a, b) = (index(s, row + 1), index(s + 1, row + 1));
+            let (c, d) = (index(s + 1, row), index(s, row));
+
+            // triangles touching a pole with two vertices are degenerate
+            if !is_pole(row + 1) {
+                data.push_triangle(a, b, c);
+            }
+
+            if !is_pole(row) {
+                data.push_triangle(a, c, d);
+            }
+        }
+    }
+}
+
+/// A flat cap at height `y`, facing up or down. UVs map x to u and the
+/// direction away from the viewer's up to v, like [`plane`].
+fn disk(data: &mut MeshData, y: f32, radius: f32, segments: u32, up: bool) {
+    let segments = segments.max(3);
+    let (normal, flip) = match up {
+        true => (glm::Vec3::y(), -1.),
+        false => (-glm::Vec3::y(), 1.),
+    };
+
+    let center = data.push_vertex(glm::vec3(0., y, 0.), normal, glm::vec2(0.5, 0.5));
+
+    let rim: Vec<u32> = (0..=segments)
+        .map(|s| {
+            let phi = angle(s, segments);
+            let uv = glm::vec2(0.5 + 0.5 * phi.sin(), 0.5 + flip * 0.5 * phi.cos());
+
+            data.push_vertex(
+                glm::vec3(radius * phi.sin(), y, radius * phi.cos()),
+                normal,
+                uv,
+            )
+        })
+        .collect();
+
+    for s in rim.windows(2) {
+        match up {
+            true => data.push_triangle(center, s[0], s[1]),
+            false => data.push_triangle(center, s[1], s[0]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all() -> Vec<(&'static str, MeshData)> {
+        vec![
+            ("cube", cube(1.)),
+            ("uv_sphere", uv_sphere(1., 16, 8)),
+            ("icosphere", icosphere(1., 2)),
+            ("plane", plane(2., 3., 3)),
+            ("cylinder", cylinder(0.5, 2., 12)),
+            ("cone", cone(0.5, 2., 12)),
+            ("torus", torus(1., 0.25, 16, 8)),
+            ("capsule", capsule(0.5, 1., 12, 4)),
+        ]
+    }
+
+    #[test]
+    fn indices_are_in_range() {
+        for (name, data) in all() {
+            assert_eq!(data.indices.len() % 3, 0, "{name}");
+            assert!(
+                data.indices
+                    .iter()
+                    .all(|&i| (i as usize) < data.vertices.len()),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn normals_are_unit_length() {
+        for (name, data) in all() {
+            for vertex in &data.vertices {
+                assert!(
+                    (vertex.normal.norm() - 1.).abs() < 1e-4,
+                    "{name}: {:?}",
+                    vertex.normal
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tangents_are_unit_length_and_orthogonal() {
+        for (name, data) in all() {
+            for vertex in &data.vertices {
+                let tangent = vertex.tangent.xyz();
+
+                assert!((tangent.norm() - 1.).abs() < 1e-4, "{name}: {tangent:?}");
+                assert!(tangent.dot(&vertex.normal).abs() < 1e-4, "{name}");
+                assert_eq!(vertex.tangent.w.abs(), 1., "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn winding_is_counter_clockwise_from_the_front() {
+        for (name, data) in all() {
+            for triangle in data.indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| &data.vertices[triangle[i] as usize]);
+                let face = (b.position - a.position).cross(&(c.position - a.position));
+
+                assert!(
+                    face.norm() > 1e-8,
+                    "{name}: degenerate triangle {triangle:?}"
+                );
+                assert!(
+                    face.dot(&(a.normal + b.normal + c.normal)) > 0.,
+                    "{name}: triangle {triangle:?} is wound against its normals"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn closed_shapes_face_outwards() {
+        for (name, data) in all()
+            .into_iter()
+            .filter(|(name, _)| *name != "torus" && *name != "plane")
+        {
+            for triangle in data.indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| data.vertices[triangle[i] as usize].position);
+                let face = (b - a).cross(&(c - a));
+
+                assert!(face.dot(&((a + b + c) / 3.)) > 0., "{name}: {triangle:?}");
+            }
+        }
+    }
+}
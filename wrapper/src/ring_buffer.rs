@@ -0,0 +1,91 @@
+use std::{marker::PhantomData, mem};
+
+use bytemuck::Pod;
+
+use crate::{
+    buffer::{Buffer, BufferType},
+    error::{Error, Result},
+    fence::Fence,
+    persistent_buffer::PersistentBuffer,
+};
+
+/// Number of regions in a [`RingBuffer`]: one being written by the CPU while
+/// up to two earlier ones are still read by the GPU.
+pub const REGIONS: usize = 3;
+
+/// Persistently mapped buffer split into [`REGIONS`] regions of `len` elements,
+/// for data that is rewritten every frame.
+///
+/// Each frame, write to [`RingBuffer::region`], issue the commands that read
+/// it at [`RingBuffer::offset`] and call [`RingBuffer::advance`]. A fence per
+/// region makes sure a region is only handed out again once the GPU is done
+/// with it.
+pub struct RingBuffer<T: Pod> {
+    buffer: PersistentBuffer<u8>,
+    len: usize,
+    // bytes between regions, aligned for `glBindBufferRange`
+    stride: usize,
+    fences: [Option<Fence>; REGIONS],
+    current: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> RingBuffer<T> {
+    pub fn new(t: BufferType, len: usize) -> Result<Self> {
+        let alignment = t.offset_alignment().max(mem::align_of::<T>());
+        let (stride, size) = len
+            .checked_mul(mem::size_of::<T>())
+            .and_then(|bytes| bytes.checked_next_multiple_of(alignment))
+            .and_then(|stride| Some((stride, stride.checked_mul(REGIONS)?)))
+            .ok_or(Error::BufferOutOfRange {
+                offset: 0,
+                len: usize::MAX,
+                size: 0,
+            })?;
+
+        Ok(Self {
+            buffer: PersistentBuffer::new(t, size)?,
+            len,
+            stride,
+            fences: Default::default(),
+            current: 0,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        self.buffer.buffer()
+    }
+
+    /// Elements per region.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Byte offset of the current region in [`RingBuffer::buffer`].
+    pub fn offset(&self) -> usize {
+        self.current * self.stride
+    }
+
+    /// The current region, waiting for the GPU to finish reading it first.
+    pub fn region(&mut self) -> &mut [T] {
+        if let Some(fence) = self.fences[self.current].take() {
+            fence.wait();
+        }
+
+        let offset = self.offset();
+        let bytes = &mut self.buffer[offset..offset + self.len * mem::size_of::<T>()];
+
+        bytemuck::cast_slice_mut(bytes)
+    }
+
+    /// Fences the commands reading the current region and moves on to the next.
+    pub fn advance(&mut self) {
+        self.fences[self.current] = Some(Fence::new());
+        self.current = (self.current + 1) % REGIONS;
+    }
+}
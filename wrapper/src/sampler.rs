@@ -0,0 +1,297 @@
+use std::{
+    ffi::{c_char, CStr},
+    sync::OnceLock,
+};
+
+use gl::types::GLenum;
+
+use nalgebra_glm as glm;
+
+// core in 4.6 and from GL_ARB_texture_filter_anisotropic, same values as the
+// EXT constants, neither of which the 4.5 bindings have
+const TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wrap {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    ClampToBorder,
+    MirrorClampToEdge,
+}
+
+impl Wrap {
+    pub fn resolve(&self) -> GLenum {
+        match self {
+            Self::Repeat => gl::REPEAT,
+            Self::MirroredRepeat => gl::MIRRORED_REPEAT,
+            Self::ClampToEdge => gl::CLAMP_TO_EDGE,
+            Self::ClampToBorder => gl::CLAMP_TO_BORDER,
+            Self::MirrorClampToEdge => gl::MIRROR_CLAMP_TO_EDGE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+impl Filter {
+    pub fn resolve(&self) -> GLenum {
+        match self {
+            Self::Nearest => gl::NEAREST,
+            Self::Linear => gl::LINEAR,
+        }
+    }
+}
+
+/// Comparison of depth textures sampled with a shadow sampler, with the
+/// reference value on the left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareFunc {
+    Never,
+    Less,
+    LessEqual,
+    Equal,
+    NotEqual,
+    GreaterEqual,
+    Greater,
+    Always,
+}
+
+impl CompareFunc {
+    pub fn resolve(&self) -> GLenum {
+        match self {
+            Self::Never => gl::NEVER,
+            Self::Less => gl::LESS,
+            Self::LessEqual => gl::LEQUAL,
+            Self::Equal => gl::EQUAL,
+            Self::NotEqual => gl::NOTEQUAL,
+            Self::GreaterEqual => gl::GEQUAL,
+            Self::Greater => gl::GREATER,
+            Self::Always => gl::ALWAYS,
+        }
+    }
+}
+
+/// How a texture is sampled. Defaults to repeating trilinear filtering.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplerDesc {
+    pub wrap_s: Wrap,
+    pub wrap_t: Wrap,
+    pub wrap_r: Wrap,
+    pub min_filter: Filter,
+    pub mag_filter: Filter,
+    /// Filter between mipmap levels, `None` to only sample the base level.
+    pub mipmap_filter: Option<Filter>,
+    /// 1 for no anisotropic filtering, clamped to what the driver supports.
+    /// Ignored without GL 4.6 or `GL_*_texture_filter_anisotropic`.
+    pub max_anisotropy: f32,
+    pub lod_bias: f32,
+    pub min_lod: f32,
+    pub max_lod: f32,
+    /// Used by [`Wrap::ClampToBorder`].
+    pub border_color: glm::Vec4,
+    /// Compare depth textures against a reference instead of returning them.
+    pub compare: Option<CompareFunc>,
+}
+
+impl Default for SamplerDesc {
+    fn default() -> Self {
+        Self {
+            wrap_s: Wrap::Repeat,
+            wrap_t: Wrap::Repeat,
+            wrap_r: Wrap::Repeat,
+            min_filter: Filter::Linear,
+            mag_filter: Filter::Linear,
+            mipmap_filter: Some(Filter::Linear),
+            max_anisotropy: 1.,
+            lod_bias: 0.,
+            min_lod: -1000.,
+            max_lod: 1000.,
+            border_color: glm::Vec4::zeros(),
+            compare: None,
+        }
+    }
+}
+
+impl SamplerDesc {
+    /// Sets the wrap mode of all coordinates.
+    pub fn wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap_s = wrap;
+        self.wrap_t = wrap;
+        self.wrap_r = wrap;
+        self
+    }
+
+    /// Sets the filter of both minification and magnification, keeping the
+    /// mipmap filter.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.min_filter = filter;
+        self.mag_filter = filter;
+        self
+    }
+
+    pub fn mipmap_filter(mut self, filter: Option<Filter>) -> Self {
+        self.mipmap_filter = filter;
+        self
+    }
+
+    pub fn anisotropy(mut self, max_anisotropy: f32) -> Self {
+        self.max_anisotropy = max_anisotropy;
+        self
+    }
+
+    pub fn compare(mut self, compare: Option<CompareFunc>) -> Self {
+        self.compare = compare;
+        self
+    }
+
+    /// `GL_TEXTURE_MIN_FILTER`, combining the min and mipmap filters.
+    pub fn resolve_min_filter(&self) -> GLenum {
+        match (self.min_filter, self.mipmap_filter) {
+            (Filter::Nearest, None) => gl::NEAREST,
+            (Filter::Linear, None) => gl::LINEAR,
+            (Filter::Nearest, Some(Filter::Nearest)) => gl::NEAREST_MIPMAP_NEAREST,
+            (Filter::Linear, Some(Filter::Nearest)) => gl::LINEAR_MIPMAP_NEAREST,
+            (Filter::Nearest, Some(Filter::Linear)) => gl::NEAREST_MIPMAP_LINEAR,
+            (Filter::Linear, Some(Filter::Linear)) => gl::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+
+    // sets every parameter through the texture or sampler parameter calls
+    pub(crate) fn apply(
+        &self,
+        int: impl Fn(GLenum, i32),
+        float: impl Fn(GLenum, f32),
+        floats: impl Fn(GLenum, &[f32]),
+    ) {
+        int(gl::TEXTURE_WRAP_S, self.wrap_s.resolve() as i32);
+        int(gl::TEXTURE_WRAP_T, self.wrap_t.resolve() as i32);
+        int(gl::TEXTURE_WRAP_R, self.wrap_r.resolve() as i32);
+        int(gl::TEXTURE_MIN_FILTER, self.resolve_min_filter() as i32);
+        int(gl::TEXTURE_MAG_FILTER, self.mag_filter.resolve() as i32);
+
+        float(gl::TEXTURE_LOD_BIAS, self.lod_bias);
+        float(gl::TEXTURE_MIN_LOD, self.min_lod);
+        float(gl::TEXTURE_MAX_LOD, self.max_lod);
+        floats(gl::TEXTURE_BORDER_COLOR, self.border_color.as_slice());
+
+        match self.compare {
+            Some(compare) => {
+                int(gl::TEXTURE_COMPARE_MODE, gl::COMPARE_REF_TO_TEXTURE as i32);
+                int(gl::TEXTURE_COMPARE_FUNC, compare.resolve() as i32);
+            }
+            None => int(gl::TEXTURE_COMPARE_MODE, gl::NONE as i32),
+        }
+
+        // always written, so lowering it takes effect, unless the driver
+        // would reject the parameter
+        if let Some(max) = max_anisotropy() {
+            float(TEXTURE_MAX_ANISOTROPY, self.max_anisotropy.clamp(1., max));
+        }
+    }
+}
+
+// the driver's anisotropy limit, `None` without GL 4.6 or the extension
+fn max_anisotropy() -> Option<f32> {
+    static MAX: OnceLock<Option<f32>> = OnceLock::new();
+
+    *MAX.get_or_init(|| {
+        let (mut major, mut minor, mut count) = (0, 0, 0);
+
+        unsafe {
+            gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+            gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+            gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+        }
+
+        let extension = (0..count as u32).any(|i| {
+            let name = unsafe { gl::GetStringi(gl::EXTENSIONS, i) };
+
+            !name.is_null()
+                && matches!(
+                    unsafe { CStr::from_ptr(name as *const c_char) }.to_bytes(),
+                    b"GL_ARB_texture_filter_anisotropic" | b"GL_EXT_texture_filter_anisotropic"
+                )
+        });
+
+        if (major, minor) < (4, 6) && !extension {
+            return None;
+        }
+
+        let mut max = 1.;
+
+        unsafe {
+            gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut max);
+        }
+
+        Some(max)
+    })
+}
+
+/// Sampler state kept apart from textures. Bound to a texture unit, it
+/// overrides the sampling parameters of whatever texture is bound there.
+pub struct Sampler {
+    id: u32,
+    desc: SamplerDesc,
+}
+
+impl Sampler {
+    pub fn new(desc: SamplerDesc) -> Self {
+        let mut id = 0;
+
+        unsafe {
+            gl::GenSamplers(1, &mut id);
+        }
+
+        let sampler = Self { id, desc };
+        sampler.apply();
+
+        sampler
+    }
+
+    fn apply(&self) {
+        let id = self.id;
+
+        self.desc.apply(
+            |name, value| unsafe { gl::SamplerParameteri(id, name, value) },
+            |name, value| unsafe { gl::SamplerParameterf(id, name, value) },
+            |name, values| unsafe { gl::SamplerParameterfv(id, name, values.as_ptr()) },
+        );
+    }
+
+    pub fn desc(&self) -> &SamplerDesc {
+        &self.desc
+    }
+
+    pub fn set_desc(&mut self, desc: SamplerDesc) {
+        self.desc = desc;
+        self.apply();
+    }
+
+    /// Binds the sampler to texture unit `unit`.
+    pub fn bind(&self, unit: u32) {
+        unsafe {
+            gl::BindSampler(unit, self.id);
+        }
+    }
+
+    /// Goes back to sampling with the parameters of the textures on `unit`.
+    pub fn unbind(unit: u32) {
+        unsafe {
+            gl::BindSampler(unit, 0);
+        }
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteSamplers(1, &self.id);
+        }
+    }
+}
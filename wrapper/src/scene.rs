@@ -0,0 +1,554 @@
+//! glTF 2.0 scenes, from `.gltf` files with external or embedded buffers and
+//! from binary `.glb` files.
+//!
+//! Everything is uploaded on load, so a GL context has to be current. Meshes
+//! use [`MeshVertex`], with missing normals and tangents generated, and
+//! materials are mapped onto [`PbrMaterial`]. Textures may use `TEXCOORD_0`
+//! or `TEXCOORD_1`, loaded as [`MeshVertex::uv`] and [`MeshVertex::uv1`].
+
+use std::{collections::HashSet, path::Path};
+
+use ::gltf::{
+    buffer::Data as BufferData,
+    camera::Projection as GltfProjection,
+    image::{Data as ImageData, Format},
+    khr_lights_punctual::Kind,
+    material::AlphaMode as GltfAlphaMode,
+    mesh::Mode,
+    texture::{Info, MagFilter, MinFilter, Sampler, WrappingMode},
+};
+use nalgebra_glm as glm;
+
+use crate::{
+    error::{Error, Result},
+    material::{AlphaMode, PbrMaterial, TextureRef},
+    mesh::{Mesh, MeshData, MeshVertex},
+    sampler::{Filter, SamplerDesc, Wrap},
+    texture::{InternalFormat, Texture, TextureDesc, TextureFormat},
+    vertex_array::Primitive,
+};
+
+/// The contents of a glTF file. Nodes, meshes, materials, textures, cameras
+/// and lights refer to each other by their index here, as in the file.
+pub struct Scene {
+    pub nodes: Vec<Node>,
+    /// Top level nodes of the default scene, or of the first one if the file
+    /// does not name a default.
+    pub roots: Vec<usize>,
+    pub meshes: Vec<SceneMesh>,
+    pub materials: Vec<PbrMaterial>,
+    /// Base color and emissive textures are sRGB, the others linear.
+    pub textures: Vec<Texture>,
+    pub cameras: Vec<SceneCamera>,
+    pub lights: Vec<Light>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub name: Option<String>,
+    /// Relative to the parent node.
+    pub transform: glm::Mat4,
+    pub children: Vec<usize>,
+    pub mesh: Option<usize>,
+    pub camera: Option<usize>,
+    pub light: Option<usize>,
+}
+
+pub struct SceneMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<ScenePrimitive>,
+}
+
+/// Part of a mesh drawn with a single material.
+pub struct ScenePrimitive {
+    pub mesh: Mesh<MeshVertex>,
+    /// Triangle strips and fans are loaded as triangle lists.
+    pub mode: Primitive,
+    /// Index into [`Scene::materials`], `None` for the default material.
+    pub material: Option<usize>,
+}
+
+impl ScenePrimitive {
+    pub fn draw(&self) {
+        self.mesh.draw(self.mode);
+    }
+}
+
+/// A camera looking down its node's local -Z axis, with +Y up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneCamera {
+    pub name: Option<String>,
+    pub projection: Projection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective {
+        /// Vertical field of view in radians.
+        yfov: f32,
+        /// Width over height, `None` to use the viewport's.
+        aspect_ratio: Option<f32>,
+        znear: f32,
+        /// `None` for an infinite projection.
+        zfar: Option<f32>,
+    },
+    Orthographic {
+        /// Half the width of the view.
+        xmag: f32,
+        /// Half the height of the view.
+        ymag: f32,
+        znear: f32,
+        zfar: f32,
+    },
+}
+
+impl Projection {
+    /// The projection matrix, with `aspect_ratio` used if the camera does not
+    /// specify its own.
+    pub fn matrix(&self, aspect_ratio: f32) -> glm::Mat4 {
+        match *self {
+            Self::Perspective {
+                yfov,
+                aspect_ratio: aspect,
+                znear,
+                zfar,
+            } => {
+                let aspect = aspect.unwrap_or(aspect_ratio);
+
+                match zfar {
+                    Some(zfar) => glm::perspective(aspect, yfov, znear, zfar),
+                    None => glm::infinite_perspective_rh_no(aspect, yfov, znear),
+                }
+            }
+            Self::Orthographic {
+                xmag,
+                ymag,
+                znear,
+                zfar,
+            } => glm::ortho(-xmag, xmag, -ymag, ymag, znear, zfar),
+        }
+    }
+}
+
+/// A `KHR_lights_punctual` light, placed by its node. Directional and spot
+/// lights shine down the node's local -Z axis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Light {
+    pub name: Option<String>,
+    pub kind: LightKind,
+    pub color: glm::Vec3,
+    /// Lux for directional lights, candela for the others.
+    pub intensity: f32,
+    /// Distance at which the light reaches zero, `None` for no cutoff.
+    pub range: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    Directional,
+    Point,
+    /// Cone angles in radians, measured from the axis.
+    Spot {
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+    },
+}
+
+impl Scene {
+    /// World transforms of all nodes, by node index.
+    pub fn world_transforms(&self) -> Vec<glm::Mat4> {
+        let mut transforms = vec![glm::Mat4::identity(); self.nodes.len()];
+        let mut has_parent = vec![false; self.nodes.len()];
+
+        for node in &self.nodes {
+            for &child in &node.children {
+                has_parent[child] = true;
+            }
+        }
+
+        let mut stack: Vec<_> = (0..self.nodes.len())
+            .filter(|&i| !has_parent[i])
+            .map(|i| (i, glm::Mat4::identity()))
+            .collect();
+
+        while let Some((i, parent)) = stack.pop() {
+            let node = &self.nodes[i];
+            transforms[i] = parent * node.transform;
+
+            stack.extend(node.children.iter().map(|&child| (child, transforms[i])));
+        }
+
+        transforms
+    }
+
+    /// Calls `f` with the world transform, primitive and material of
+    /// everything drawn by the nodes under [`Scene::roots`].
+    pub fn visit_primitives(
+        &self,
+        mut f: impl FnMut(&glm::Mat4, &ScenePrimitive, Option<&PbrMaterial>),
+    ) {
+        let transforms = self.world_transforms();
+        let mut stack = self.roots.clone();
+
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            stack.extend(&node.children);
+
+            let Some(mesh) = node.mesh else { continue };
+
+            for primitive in &self.meshes[mesh].primitives {
+                let material = primitive.material.map(|i| &self.materials[i]);
+                f(&transforms[i], primitive, material);
+            }
+        }
+    }
+}
+
+/// Loads a `.gltf` or `.glb` file with its buffers and images.
+pub fn load(path: impl AsRef<Path>) -> Result<Scene> {
+    let (document, buffers, images) = ::gltf::import(path)?;
+
+    let nodes = document
+        .nodes()
+        .map(|node| Node {
+            name: node.name().map(String::from),
+            transform: glm::Mat4::from(node.transform().matrix()),
+            children: node.children().map(|child| child.index()).collect(),
+            mesh: node.mesh().map(|mesh| mesh.index()),
+            camera: node.camera().map(|camera| camera.index()),
+            light: node.light().map(|light| light.index()),
+        })
+        .collect();
+
+    let roots = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .map(|scene| scene.nodes().map(|node| node.index()).collect())
+        .unwrap_or_default();
+
+    let meshes = document
+        .meshes()
+        .map(|mesh| SceneMesh {
+            name: mesh.name().map(String::from),
+            primitives: mesh
+                .primitives()
+                .filter_map(|primitive| load_primitive(&primitive, &buffers))
+                .collect(),
+        })
+        .collect();
+
+    let materials: Vec<PbrMaterial> = document
+        .materials()
+        .map(|material| {
+            let pbr = material.pbr_metallic_roughness();
+
+            PbrMaterial {
+                name: material.name().map(String::from),
+                base_color: pbr.base_color_factor().into(),
+                base_color_texture: pbr.base_color_texture().map(texture_ref),
+                metallic: pbr.metallic_factor(),
+                roughness: pbr.roughness_factor(),
+                metallic_roughness_texture: pbr.metallic_roughness_texture().map(texture_ref),
+                normal_texture: material.normal_texture().map(|info| TextureRef {
+                    texture: info.texture().index(),
+                    uv_set: info.tex_coord(),
+                }),
+                normal_scale: material.normal_texture().map_or(1., |info| info.scale()),
+                occlusion_texture: material.occlusion_texture().map(|info| TextureRef {
+                    texture: info.texture().index(),
+                    uv_set: info.tex_coord(),
+                }),
+                occlusion_strength: material
+                    .occlusion_texture()
+                    .map_or(1., |info| info.strength()),
+                emissive: material.emissive_factor().into(),
+                emissive_texture: material.emissive_texture().map(texture_ref),
+                alpha_mode: match material.alpha_mode() {
+                    GltfAlphaMode::Opaque => AlphaMode::Opaque,
+                    GltfAlphaMode::Mask => AlphaMode::Mask(material.alpha_cutoff().unwrap_or(0.5)),
+                    GltfAlphaMode::Blend => AlphaMode::Blend,
+                },
+                double_sided: material.double_sided(),
+            }
+        })
+        .collect();
+
+    // only TEXCOORD_0 and TEXCOORD_1 end up in the vertices
+    for material in &materials {
+        let textures = [
+            material.base_color_texture,
+            material.metallic_roughness_texture,
+            material.normal_texture,
+            material.occlusion_texture,
+            material.emissive_texture,
+        ];
+
+        if let Some(texture) = textures.into_iter().flatten().find(|t| t.uv_set > 1) {
+            return Err(Error::UnsupportedUvSet {
+                material: material.name.clone(),
+                uv_set: texture.uv_set,
+            });
+        }
+    }
+
+    // color textures are sRGB encoded, the others hold linear data; a
+    // texture used both ways is loaded as color
+    let srgb: HashSet<_> = materials
+        .iter()
+        .flat_map(|material| [material.base_color_texture, material.emissive_texture])
+        .flatten()
+        .map(|texture| texture.texture)
+        .collect();
+
+    let textures = document
+        .textures()
+        .map(|texture| {
+            let image = &images[texture.source().index()];
+
+            let internal_format = match srgb.contains(&texture.index()) {
+                true => InternalFormat::Srgb8Alpha8,
+                false => InternalFormat::Rgba8,
+            };
+
+            // glTF UVs start at the top left, like the rows of the image, so
+            // no flip is needed
+            let desc = TextureDesc::new(image.width, image.height, internal_format)
+                .sampler(sampler_desc(&texture.sampler()));
+
+            Texture::new(desc, TextureFormat::Rgba, &rgba8(image))
+        })
+        .collect::<Result<_>>()?;
+
+    let cameras = document
+        .cameras()
+        .map(|camera| SceneCamera {
+            name: camera.name().map(String::from),
+            projection: match camera.projection() {
+                GltfProjection::Perspective(perspective) => Projection::Perspective {
+                    yfov: perspective.yfov(),
+                    aspect_ratio: perspective.aspect_ratio(),
+                    znear: perspective.znear(),
+                    zfar: perspective.zfar(),
+                },
+                GltfProjection::Orthographic(orthographic) => Projection::Orthographic {
+                    xmag: orthographic.xmag(),
+                    ymag: orthographic.ymag(),
+                    znear: orthographic.znear(),
+                    zfar: orthographic.zfar(),
+                },
+            },
+        })
+        .collect();
+
+    let lights = document
+        .lights()
+        .into_iter()
+        .flatten()
+        .map(|light| Light {
+            name: light.name().map(String::from),
+            kind: match light.kind() {
+                Kind::Directional => LightKind::Directional,
+                Kind::Point => LightKind::Point,
+                Kind::Spot {
+                    inner_cone_angle,
+                    outer_cone_angle,
+                } => LightKind::Spot {
+                    inner_cone_angle,
+                    outer_cone_angle,
+                },
+            },
+            color: light.color().into(),
+            intensity: light.intensity(),
+            range: light.range(),
+        })
+        .collect();
+
+    Ok(Scene {
+        nodes,
+        roots,
+        meshes,
+        materials,
+        textures,
+        cameras,
+        lights,
+    })
+}
+
+/// Reads a primitive as a mesh, `None` if it has no positions.
+fn load_primitive(primitive: &::gltf::Primitive, buffers: &[BufferData]) -> Option<ScenePrimitive> {
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+    let mut data = MeshData::new();
+    data.vertices = reader
+        .read_positions()?
+        .map(|position| MeshVertex {
+            position: position.into(),
+            normal: glm::Vec3::zeros(),
+            uv: glm::Vec2::zeros(),
+            tangent: glm::Vec4::zeros(),
+            uv1: glm::Vec2::zeros(),
+        })
+        .collect();
+
+    if let Some(uvs) = reader.read_tex_coords(0) {
+        for (vertex, uv) in data.vertices.iter_mut().zip(uvs.into_f32()) {
+            vertex.uv = uv.into();
+        }
+    }
+
+    match reader.read_tex_coords(1) {
+        Some(uvs) => {
+            for (vertex, uv) in data.vertices.iter_mut().zip(uvs.into_f32()) {
+                vertex.uv1 = uv.into();
+            }
+        }
+        None => {
+            for vertex in &mut data.vertices {
+                vertex.uv1 = vertex.uv;
+            }
+        }
+    }
+
+    let indices: Vec<u32> = match reader.read_indices() {
+        Some(indices) => indices.into_u32().collect(),
+        None => (0..data.vertices.len() as u32).collect(),
+    };
+
+    let (mode, indices) = match primitive.mode() {
+        Mode::Points => (Primitive::Points, indices),
+        Mode::Lines => (Primitive::Lines, indices),
+        Mode::LineLoop => (Primitive::LineLoop, indices),
+        Mode::LineStrip => (Primitive::LineStrip, indices),
+        Mode::Triangles => (Primitive::Triangles, indices),
+        Mode::TriangleStrip => (Primitive::Triangles, strip_triangles(&indices)),
+        Mode::TriangleFan => (Primitive::Triangles, fan_triangles(&indices)),
+    };
+    data.indices = indices;
+
+    // normals and tangents can only be generated for triangles
+    let triangles = mode == Primitive::Triangles;
+
+    match reader.read_normals() {
+        Some(normals) => {
+            for (vertex, normal) in data.vertices.iter_mut().zip(normals) {
+                vertex.normal = normal.into();
+            }
+        }
+        None if triangles => data.compute_normals(),
+        None => {}
+    }
+
+    match reader.read_tangents() {
+        Some(tangents) => {
+            for (vertex, tangent) in data.vertices.iter_mut().zip(tangents) {
+                vertex.tangent = tangent.into();
+            }
+        }
+        None if triangles => data.compute_tangents(),
+        None => {}
+    }
+
+    Some(ScenePrimitive {
+        mesh: data.into_mesh(),
+        mode,
+        material: primitive.material().index(),
+    })
+}
+
+fn texture_ref(info: Info) -> TextureRef {
+    TextureRef {
+        texture: info.texture().index(),
+        uv_set: info.tex_coord(),
+    }
+}
+
+fn sampler_desc(sampler: &Sampler) -> SamplerDesc {
+    let wrap = |mode| match mode {
+        WrappingMode::ClampToEdge => Wrap::ClampToEdge,
+        WrappingMode::MirroredRepeat => Wrap::MirroredRepeat,
+        WrappingMode::Repeat => Wrap::Repeat,
+    };
+
+    let (min_filter, mipmap_filter) = match sampler.min_filter() {
+        Some(MinFilter::Nearest) => (Filter::Nearest, None),
+        Some(MinFilter::Linear) => (Filter::Linear, None),
+        Some(MinFilter::NearestMipmapNearest) => (Filter::Nearest, Some(Filter::Nearest)),
+        Some(MinFilter::LinearMipmapNearest) => (Filter::Linear, Some(Filter::Nearest)),
+        Some(MinFilter::NearestMipmapLinear) => (Filter::Nearest, Some(Filter::Linear)),
+        Some(MinFilter::LinearMipmapLinear) | None => (Filter::Linear, Some(Filter::Linear)),
+    };
+
+    SamplerDesc {
+        wrap_s: wrap(sampler.wrap_s()),
+        wrap_t: wrap(sampler.wrap_t()),
+        min_filter,
+        mag_filter: match sampler.mag_filter() {
+            Some(MagFilter::Nearest) => Filter::Nearest,
+            Some(MagFilter::Linear) | None => Filter::Linear,
+        },
+        mipmap_filter,
+        ..SamplerDesc::default()
+    }
+}
+
+fn strip_triangles(indices: &[u32]) -> Vec<u32> {
+    indices
+        .windows(3)
+        .enumerate()
+        .flat_map(|(i, w)| match i % 2 {
+            // every other triangle is wound the other way round
+            0 => [w[0], w[1], w[2]],
+            _ => [w[1], w[0], w[2]],
+        })
+        .collect()
+}
+
+fn fan_triangles(indices: &[u32]) -> Vec<u32> {
+    indices
+        .windows(2)
+        .skip(1)
+        .flat_map(|w| [indices[0], w[0], w[1]])
+        .collect()
+}
+
+// expands any glTF image format to 8 bit RGBA
+fn rgba8(image: &ImageData) -> Vec<u8> {
+    let (channels, depth) = match image.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 => (3, 1),
+        Format::R8G8B8A8 => return image.pixels.clone(),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+        Format::R32G32B32FLOAT => (3, 4),
+        Format::R32G32B32A32FLOAT => (4, 4),
+    };
+
+    image
+        .pixels
+        .chunks_exact(channels * depth)
+        .flat_map(|pixel| {
+            let mut values = pixel.chunks_exact(depth).map(|bytes| match depth {
+                1 => bytes[0],
+                2 => (u16::from_ne_bytes([bytes[0], bytes[1]]) >> 8) as u8,
+                _ => {
+                    let value = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    (value.clamp(0., 1.) * 255.).round() as u8
+                }
+            });
+
+            let mut next = || values.next().unwrap_or(u8::MAX);
+
+            match channels {
+                // grey, with alpha for two channels
+                1 | 2 => {
+                    let grey = next();
+                    [grey, grey, grey, next()]
+                }
+                _ => [next(), next(), next(), next()],
+            }
+        })
+        .collect()
+}
@@ -1,23 +1,97 @@
-use gl::types::GLchar;
+use gl::types::{GLbitfield, GLchar, GLenum};
 
-use std::ffi::CString;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CStr, CString};
+use std::fmt::Write;
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
 use std::ptr;
 
-use std::fs::File;
-use std::io::prelude::*;
-use std::path::Path;
-
 use nalgebra_glm as glm;
 
+use crate::{
+    diagnostic,
+    error::{Error, Result},
+    uniform::{self, Uniform, UniformInfo},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ShaderType {
     Fragment,
     Vertex,
+    Geometry,
+    TessControl,
+    TessEvaluation,
+    Compute,
+}
+
+impl ShaderType {
+    pub fn resolve(&self) -> GLenum {
+        match self {
+            Self::Fragment => gl::FRAGMENT_SHADER,
+            Self::Vertex => gl::VERTEX_SHADER,
+            Self::Geometry => gl::GEOMETRY_SHADER,
+            Self::TessControl => gl::TESS_CONTROL_SHADER,
+            Self::TessEvaluation => gl::TESS_EVALUATION_SHADER,
+            Self::Compute => gl::COMPUTE_SHADER,
+        }
+    }
+}
+
+/// Memory barriers for [`ShaderProgram::memory_barrier`], named after the
+/// `GL_*_BARRIER_BIT` they stand for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Barrier {
+    VertexAttribArray,
+    ElementArray,
+    Uniform,
+    TextureFetch,
+    ShaderImageAccess,
+    Command,
+    PixelBuffer,
+    TextureUpdate,
+    BufferUpdate,
+    Framebuffer,
+    TransformFeedback,
+    AtomicCounter,
+    ShaderStorage,
+    ClientMappedBuffer,
+    QueryBuffer,
+    All,
+}
+
+impl Barrier {
+    pub fn resolve(&self) -> GLbitfield {
+        match self {
+            Self::VertexAttribArray => gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT,
+            Self::ElementArray => gl::ELEMENT_ARRAY_BARRIER_BIT,
+            Self::Uniform => gl::UNIFORM_BARRIER_BIT,
+            Self::TextureFetch => gl::TEXTURE_FETCH_BARRIER_BIT,
+            Self::ShaderImageAccess => gl::SHADER_IMAGE_ACCESS_BARRIER_BIT,
+            Self::Command => gl::COMMAND_BARRIER_BIT,
+            Self::PixelBuffer => gl::PIXEL_BUFFER_BARRIER_BIT,
+            Self::TextureUpdate => gl::TEXTURE_UPDATE_BARRIER_BIT,
+            Self::BufferUpdate => gl::BUFFER_UPDATE_BARRIER_BIT,
+            Self::Framebuffer => gl::FRAMEBUFFER_BARRIER_BIT,
+            Self::TransformFeedback => gl::TRANSFORM_FEEDBACK_BARRIER_BIT,
+            Self::AtomicCounter => gl::ATOMIC_COUNTER_BARRIER_BIT,
+            Self::ShaderStorage => gl::SHADER_STORAGE_BARRIER_BIT,
+            Self::ClientMappedBuffer => gl::CLIENT_MAPPED_BUFFER_BARRIER_BIT,
+            Self::QueryBuffer => gl::QUERY_BUFFER_BARRIER_BIT,
+            Self::All => gl::ALL_BARRIER_BITS,
+        }
+    }
 }
 
 pub struct Shader {
     id: u32,
     //t == type, bruh
     t: ShaderType,
+    files: Vec<PathBuf>,
+    // kept to key the program binary cache
+    source: String,
 }
 
 impl Drop for Shader {
@@ -29,44 +103,274 @@ impl Drop for Shader {
 }
 
 impl Shader {
-    pub fn new(source: &str, t: ShaderType) -> Shader {
-        let mut shader = Shader { id: 0, t };
+    pub fn new(source: &str, t: ShaderType) -> Result<Shader> {
+        Self::compile(source, t, Vec::new())
+    }
 
-        unsafe {
-            shader.id = match shader.t {
-                ShaderType::Vertex => gl::CreateShader(gl::VERTEX_SHADER),
-                ShaderType::Fragment => gl::CreateShader(gl::FRAGMENT_SHADER),
-            };
+    /// Loads the file through a default [`Preprocessor`], so `#include` works.
+    pub fn from_file(file_path: impl AsRef<Path>, t: ShaderType) -> Result<Shader> {
+        Self::from_preprocessed(Preprocessor::new().process_file(file_path)?, t)
+    }
 
-            let c_str_source = CString::new(source.as_bytes()).unwrap();
+    pub fn from_preprocessed(source: PreprocessedSource, t: ShaderType) -> Result<Shader> {
+        Self::compile(&source.source, t, source.files)
+    }
+
+    pub fn shader_type(&self) -> ShaderType {
+        self.t
+    }
+
+    /// Every file this shader was built from, including `#include`d ones.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    // `files` maps GLSL source string numbers back to files for diagnostics
+    fn compile(source: &str, t: ShaderType, files: Vec<PathBuf>) -> Result<Shader> {
+        let c_str_source = CString::new(source.as_bytes())?;
+
+        let mut shader = Shader {
+            id: 0,
+            t,
+            files,
+            source: source.to_owned(),
+        };
+
+        unsafe {
+            shader.id = gl::CreateShader(shader.t.resolve());
 
             gl::ShaderSource(shader.id, 1, &c_str_source.as_ptr(), ptr::null());
 
             gl::CompileShader(shader.id);
-            check_errors(shader.id, true);
         };
 
-        shader
+        // on failure `shader` is dropped here, which deletes the GL object
+        check_errors(shader.id, true).map_err(|log| Error::ShaderCompile {
+            stage: t,
+            diagnostics: diagnostic::parse_log(&log, &shader.files),
+            log,
+        })?;
+
+        Ok(shader)
     }
+}
 
-    pub fn from_file(file_path: &str, t: ShaderType) -> Shader {
-        let path = Path::new(file_path);
-        let mut file = File::open(path).unwrap();
+/// Resolves `#include "file"` and injects `#define`s into GLSL sources.
+///
+/// Every file gets its own source string number in the emitted `#line`
+/// directives, so compile errors map back through [`PreprocessedSource::files`].
+/// A file is included only once if it has `#pragma once` or an
+/// `#ifndef X`/`#define X` guard; including a file that is already being
+/// processed without either is an error.
+#[derive(Debug, Clone, Default)]
+pub struct Preprocessor {
+    defines: Vec<(String, String)>,
+}
 
-        let mut s = String::new();
-        file.read_to_string(&mut s).unwrap();
+#[derive(Debug, Clone)]
+pub struct PreprocessedSource {
+    pub source: String,
+    /// `files[n]` is the file behind GLSL source string number `n`.
+    pub files: Vec<PathBuf>,
+}
 
-        Self::new(s.as_str(), t)
+#[derive(Default)]
+struct PreprocessState {
+    output: String,
+    files: Vec<PathBuf>,
+    stack: Vec<PathBuf>,
+    once: HashSet<PathBuf>,
+    guards: HashSet<String>,
+}
+
+impl Preprocessor {
+    pub fn new() -> Self {
+        Self::default()
     }
+
+    /// Adds `#define name value` right after the `#version` line.
+    pub fn define(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.defines.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn process_file(&self, path: impl AsRef<Path>) -> Result<PreprocessedSource> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+
+        let mut state = PreprocessState::default();
+        self.process(&mut state, path, &source, true)?;
+
+        Ok(PreprocessedSource {
+            source: state.output,
+            files: state.files,
+        })
+    }
+
+    fn process(
+        &self,
+        state: &mut PreprocessState,
+        path: &Path,
+        source: &str,
+        root: bool,
+    ) -> Result<()> {
+        let index = state.files.len();
+        state.files.push(path.to_path_buf());
+        state.stack.push(canonical(path));
+
+        if let Some(guard) = include_guard(source) {
+            state.guards.insert(guard);
+        }
+
+        // only the root file may declare a version, defines have to follow it
+        let version_line = root
+            .then(|| {
+                source
+                    .lines()
+                    .position(|line| directive(line).0 == "version")
+            })
+            .flatten();
+
+        if root && version_line.is_none() {
+            self.write_defines(state, 1, index);
+        }
+
+        for (i, line) in source.lines().enumerate() {
+            let line_number = i as u32 + 1;
+
+            match directive(line) {
+                ("include", argument) => {
+                    self.include(state, path, line_number, argument)?;
+                    writeln!(state.output, "#line {} {index}", line_number + 1).unwrap();
+                }
+                ("pragma", "once") => {
+                    state.once.insert(canonical(path));
+                    state.output.push('\n');
+                }
+                ("version", _) if !root => state.output.push('\n'),
+                _ => {
+                    state.output.push_str(line);
+                    state.output.push('\n');
+                }
+            }
+
+            if Some(i) == version_line {
+                self.write_defines(state, line_number + 1, index);
+            }
+        }
+
+        state.stack.pop();
+
+        Ok(())
+    }
+
+    fn include(
+        &self,
+        state: &mut PreprocessState,
+        from: &Path,
+        line: u32,
+        argument: &str,
+    ) -> Result<()> {
+        let error = |message: String| Error::Preprocess {
+            file: from.to_path_buf(),
+            line,
+            message,
+        };
+
+        let name = argument
+            .strip_prefix('"')
+            .and_then(|rest| rest.strip_suffix('"'))
+            .or_else(|| argument.strip_prefix('<')?.strip_suffix('>'))
+            .ok_or_else(|| error(format!("malformed #include {argument}")))?;
+
+        let path = from.parent().unwrap_or(Path::new("")).join(name);
+        let key = canonical(&path);
+
+        if state.once.contains(&key) {
+            return Ok(());
+        }
+
+        let source = fs::read_to_string(&path)
+            .map_err(|err| error(format!("cannot include `{}`: {err}", path.display())))?;
+
+        if include_guard(&source).is_some_and(|guard| state.guards.contains(&guard)) {
+            return Ok(());
+        }
+
+        if state.stack.contains(&key) {
+            let mut cycle = state.stack.clone();
+            cycle.push(key);
+
+            return Err(Error::IncludeCycle(cycle));
+        }
+
+        writeln!(state.output, "#line 1 {}", state.files.len()).unwrap();
+        self.process(state, &path, &source, false)
+    }
+
+    fn write_defines(&self, state: &mut PreprocessState, next_line: u32, index: usize) {
+        if self.defines.is_empty() {
+            return;
+        }
+
+        for (name, value) in &self.defines {
+            writeln!(state.output, "#define {name} {value}").unwrap();
+        }
+
+        writeln!(state.output, "#line {next_line} {index}").unwrap();
+    }
+}
+
+// splits `#  include "file"` into ("include", "\"file\""), non-directives give ("", "")
+fn directive(line: &str) -> (&str, &str) {
+    let Some(rest) = line.trim().strip_prefix('#') else {
+        return ("", "");
+    };
+
+    let rest = rest.trim_start();
+    let end = rest
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(rest.len());
+
+    (&rest[..end], rest[end..].trim())
+}
+
+// `#ifndef X` directly followed by `#define X` as the first directives of a file
+fn include_guard(source: &str) -> Option<String> {
+    let mut directives = source
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.trim().starts_with("//"))
+        .map(directive);
+
+    let ("ifndef", guard) = directives.next()? else {
+        return None;
+    };
+    let ("define", defined) = directives.next()? else {
+        return None;
+    };
+
+    (defined.split_whitespace().next() == Some(guard)).then(|| guard.to_owned())
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
 }
 
 pub struct Builder {
     id: u32,
     attached_shaders: Vec<Shader>,
+    binary_cache: Option<PathBuf>,
 }
 
 pub struct ShaderProgram {
     pub id: u32,
+    compute: bool,
+    cache_file: Option<PathBuf>,
+    uniforms: HashMap<String, UniformInfo>,
+    // names whose errors were already recorded, so per-frame setters add
+    // each only once
+    reported: RefCell<HashSet<String>>,
+    uniform_errors: RefCell<Vec<Error>>,
 }
 
 impl Builder {
@@ -75,10 +379,25 @@ impl Builder {
             Self {
                 id: gl::CreateProgram(),
                 attached_shaders: Vec::new(),
+                binary_cache: None,
             }
         }
     }
 
+    /// Caches the linked program binary in `dir`, named after the shader
+    /// sources after preprocessing. The file is replaced once the GL vendor,
+    /// renderer or version change. A
+    /// [`ReloadableProgram`](crate::hot_reload::ReloadableProgram) removes the
+    /// binary of the sources it replaces, so edits don't pile up.
+    ///
+    /// The key is computed from the attached shaders, which are already
+    /// compiled, so a hit only saves linking. A binary the driver rejects is
+    /// ignored and the program is linked from source as usual.
+    pub fn binary_cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.binary_cache = Some(dir.into());
+        self
+    }
+
     pub fn attach(mut self, shader: Shader) -> Self {
         unsafe {
             gl::AttachShader(self.id, shader.id);
@@ -89,19 +408,193 @@ impl Builder {
         self
     }
 
-    pub fn link(self) -> ShaderProgram {
-        unsafe {
-            gl::LinkProgram(self.id);
+    pub fn link(mut self) -> Result<ShaderProgram> {
+        let stages: Vec<ShaderType> = self.attached_shaders.iter().map(|s| s.t).collect();
+
+        validate_stages(&stages).map_err(Error::InvalidStages)?;
+
+        let cache = self
+            .binary_cache
+            .as_ref()
+            .map(|dir| dir.join(format!("{:016x}.bin", self.cache_name())));
+        let key = self.cache_key();
 
-            check_errors(self.id, false);
+        let status = match cache
+            .as_deref()
+            .is_some_and(|path| load_binary(self.id, path, key))
+        {
+            true => Ok(()),
+            false => {
+                unsafe {
+                    if cache.is_some() {
+                        gl::ProgramParameteri(
+                            self.id,
+                            gl::PROGRAM_BINARY_RETRIEVABLE_HINT,
+                            gl::TRUE as i32,
+                        );
+                    }
+
+                    gl::LinkProgram(self.id);
+                }
+
+                let status = check_errors(self.id, false);
 
-            for shader in self.attached_shaders {
-                drop(shader);
+                if let (Ok(()), Some(path)) = (&status, &cache) {
+                    store_binary(self.id, path, key);
+                }
+
+                status
             }
+        };
+
+        self.attached_shaders.clear();
+        status.map_err(|log| Error::ProgramLink { log })?;
+
+        // the program now belongs to the `ShaderProgram`, not the builder
+        let id = mem::replace(&mut self.id, 0);
+
+        Ok(ShaderProgram {
+            id,
+            compute: stages.contains(&ShaderType::Compute),
+            cache_file: cache,
+            uniforms: uniform::reflect(id),
+            reported: RefCell::new(HashSet::new()),
+            uniform_errors: RefCell::new(Vec::new()),
+        })
+    }
+}
+
+impl Drop for Builder {
+    fn drop(&mut self) {
+        // 0 once linked, which GL ignores
+        unsafe {
+            gl::DeleteProgram(self.id);
         }
+    }
+}
+
+impl Builder {
+    // names the cache file after the sources, so programs built from the
+    // same files with different defines don't share one
+    fn cache_name(&self) -> u64 {
+        let mut hash = FNV_OFFSET;
+
+        for shader in &self.attached_shaders {
+            hash = fnv1a(hash, &shader.t.resolve().to_le_bytes());
+            hash = fnv1a(hash, shader.source.as_bytes());
+            hash = fnv1a(hash, &[0]);
+        }
+
+        hash
+    }
+
+    // stored in the cache file, a binary is only used if it matches
+    fn cache_key(&self) -> u64 {
+        let mut hash = FNV_OFFSET;
+
+        for name in [gl::VENDOR, gl::RENDERER, gl::VERSION] {
+            hash = fnv1a(hash, &gl_string(name));
+            hash = fnv1a(hash, &[0]);
+        }
+
+        for shader in &self.attached_shaders {
+            hash = fnv1a(hash, &shader.t.resolve().to_le_bytes());
+            hash = fnv1a(hash, shader.source.as_bytes());
+            hash = fnv1a(hash, &[0]);
+        }
+
+        hash
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+
+// FNV-1a, stable across Rust versions unlike `DefaultHasher`
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
+
+fn gl_string(name: GLenum) -> Vec<u8> {
+    unsafe {
+        let ptr = gl::GetString(name);
+
+        match ptr.is_null() {
+            true => Vec::new(),
+            false => CStr::from_ptr(ptr as *const _).to_bytes().to_vec(),
+        }
+    }
+}
+
+// cache files are the key as a little endian u64, the binary format as a
+// little endian u32 and then the binary
+fn load_binary(program: u32, path: &Path, key: u64) -> bool {
+    let Ok(data) = fs::read(path) else {
+        return false;
+    };
+
+    if data.len() < 12 || data[..8] != key.to_le_bytes() {
+        return false;
+    }
+
+    let (format, binary) = data[8..].split_at(4);
+    let format = u32::from_le_bytes(format.try_into().unwrap());
+    let mut success = i32::from(gl::FALSE);
+
+    unsafe {
+        gl::ProgramBinary(
+            program,
+            format,
+            binary.as_ptr() as *const _,
+            binary.len() as i32,
+        );
+        gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+    }
+
+    success == i32::from(gl::TRUE)
+}
+
+// failing to write the cache only costs a link next time, so errors are ignored
+fn store_binary(program: u32, path: &Path, key: u64) {
+    let mut length = 0;
+
+    unsafe {
+        gl::GetProgramiv(program, gl::PROGRAM_BINARY_LENGTH, &mut length);
+    }
+
+    if length <= 0 {
+        return;
+    }
+
+    let mut binary = vec![0u8; length as usize];
+    let mut written = 0;
+    let mut format = 0;
+
+    unsafe {
+        gl::GetProgramBinary(
+            program,
+            length,
+            &mut written,
+            &mut format,
+            binary.as_mut_ptr() as *mut _,
+        );
+    }
+
+    binary.truncate(written as usize);
+
+    let mut data = key.to_le_bytes().to_vec();
+    data.extend_from_slice(&format.to_le_bytes());
+    data.extend_from_slice(&binary);
 
-        ShaderProgram { id: self.id }
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
     }
+
+    let _ = fs::write(path, data);
 }
 
 impl ShaderProgram {
@@ -113,29 +606,158 @@ impl ShaderProgram {
         unsafe { gl::UseProgram(self.id) }
     }
 
+    pub fn is_compute(&self) -> bool {
+        self.compute
+    }
+
+    /// Local work group size declared with `layout(local_size_x = ...)`.
+    pub fn work_group_size(&self) -> [i32; 3] {
+        debug_assert!(self.compute, "work_group_size on a graphics program");
+
+        let mut size = [0; 3];
+
+        unsafe {
+            gl::GetProgramiv(self.id, gl::COMPUTE_WORK_GROUP_SIZE, size.as_mut_ptr());
+        }
+
+        size
+    }
+
+    /// Binds the program and launches `x * y * z` work groups.
+    pub fn dispatch(&self, x: u32, y: u32, z: u32) {
+        debug_assert!(self.compute, "dispatch on a graphics program");
+
+        self.use_program();
+
+        unsafe {
+            gl::DispatchCompute(x, y, z);
+        }
+    }
+
+    pub fn memory_barrier(barriers: &[Barrier]) {
+        let bits = barriers.iter().fold(0, |bits, b| bits | b.resolve());
+
+        unsafe {
+            gl::MemoryBarrier(bits);
+        }
+    }
+
+    // where the binary cache keeps this program, if it has one
+    pub(crate) fn cache_file(&self) -> Option<&Path> {
+        self.cache_file.as_deref()
+    }
+
+    /// Active uniforms found when the program was linked.
+    pub fn uniforms(&self) -> &HashMap<String, UniformInfo> {
+        &self.uniforms
+    }
+
+    pub fn uniform(&self, name: &str) -> Result<&UniformInfo> {
+        self.uniforms
+            .get(name)
+            .ok_or_else(|| Error::UniformNotFound(name.to_owned()))
+    }
+
+    pub fn uniform_location(&self, name: &str) -> Result<i32> {
+        self.uniform(name).map(|info| info.location)
+    }
+
+    fn uniform_block_index(&self, name: &str) -> Result<u32> {
+        let c_str = CString::new(name.as_bytes())?;
+
+        match unsafe { gl::GetUniformBlockIndex(self.id, c_str.as_ptr()) } {
+            gl::INVALID_INDEX => Err(Error::UniformBlockNotFound(name.to_owned())),
+            index => Ok(index),
+        }
+    }
+
+    /// Size in bytes the driver computed for the uniform block `name`.
+    pub fn uniform_block_size(&self, name: &str) -> Result<usize> {
+        let index = self.uniform_block_index(name)?;
+        let mut size = 0;
+
+        unsafe {
+            gl::GetActiveUniformBlockiv(self.id, index, gl::UNIFORM_BLOCK_DATA_SIZE, &mut size);
+        }
+
+        Ok(size as usize)
+    }
+
+    /// Makes the uniform block `name` read from the buffer bound to `binding`.
+    pub fn bind_uniform_block(&self, name: &str, binding: u32) -> Result<()> {
+        let index = self.uniform_block_index(name)?;
+
+        unsafe {
+            gl::UniformBlockBinding(self.id, index, binding);
+        }
+
+        Ok(())
+    }
+
+    /// Makes the shader storage block `name` use the buffer bound to `binding`.
+    pub fn bind_storage_block(&self, name: &str, binding: u32) -> Result<()> {
+        let c_str = CString::new(name.as_bytes())?;
+
+        let index = unsafe {
+            gl::GetProgramResourceIndex(self.id, gl::SHADER_STORAGE_BLOCK, c_str.as_ptr())
+        };
+
+        if index == gl::INVALID_INDEX {
+            return Err(Error::StorageBlockNotFound(name.to_owned()));
+        }
+
+        unsafe {
+            gl::ShaderStorageBlockBinding(self.id, index, binding);
+        }
+
+        Ok(())
+    }
+
+    /// Writes `value` to the uniform `name` of this program, which has to be
+    /// bound with [`ShaderProgram::use_program`].
+    ///
+    /// Unknown names, values whose type doesn't match the declaration and
+    /// arrays longer than the uniform's are skipped, and kept for [`ShaderProgram::take_uniform_errors`].
+    pub fn set_uniform<T: Uniform + ?Sized>(&self, name: &str, value: &T) {
+        if let Err(err) = self.try_set_uniform(name, value) {
+            if self.reported.borrow_mut().insert(name.to_owned()) {
+                self.uniform_errors.borrow_mut().push(err);
+            }
+        }
+    }
+
+    /// Like [`ShaderProgram::set_uniform`], but fails on unknown names,
+    /// mismatched types and more elements than the uniform array holds.
+    pub fn try_set_uniform<T: Uniform + ?Sized>(&self, name: &str, value: &T) -> Result<()> {
+        let info = self.uniform(name)?;
+        let location = uniform::check(name, info, T::GL_TYPE, value.elements())?;
+        value.upload(location);
+
+        Ok(())
+    }
+
+    /// Errors skipped by [`ShaderProgram::set_uniform`] since the last call,
+    /// at most one per uniform name over the program's lifetime.
+    pub fn take_uniform_errors(&self) -> Vec<Error> {
+        mem::take(&mut self.uniform_errors.borrow_mut())
+    }
+
     //common used uniforms
 
     pub fn set_vec3f(&self, name: &str, vec: &glm::Vec3) {
-        uniform!(self, Uniform3fv, name, 1, glm::value_ptr(vec).as_ptr());
+        self.set_uniform(name, vec);
     }
 
     pub fn set_mat4f(&self, name: &str, mat: &glm::Mat4) {
-        uniform!(
-            self,
-            UniformMatrix4fv,
-            name,
-            1,
-            gl::FALSE,
-            glm::value_ptr(mat).as_ptr()
-        );
+        self.set_uniform(name, mat);
     }
 
     pub fn set_float(&self, name: &str, value: f32) {
-        uniform!(self, Uniform1f, name, value);
+        self.set_uniform(name, &value);
     }
 
     pub fn set_uint(&self, name: &str, value: u32) {
-        uniform!(self, Uniform1ui, name, value);
+        self.set_uniform(name, &value);
     }
 }
 
@@ -147,25 +769,36 @@ impl Drop for ShaderProgram {
     }
 }
 
-#[macro_export]
-macro_rules! uniform {
-    ($self:ident, $uniform_name:ident, $name:expr, $($arg:expr),+) => {
-        {
-            let c_str = CString::new($name.as_bytes()).unwrap();
+fn validate_stages(stages: &[ShaderType]) -> std::result::Result<(), String> {
+    let has = |t| stages.contains(&t);
 
-            unsafe {
-                gl::$uniform_name(gl::GetUniformLocation($self.id, c_str.as_ptr()), $($arg), +);
-            }
-        }
-    };
-}
+    if stages.is_empty() {
+        return Err("no shaders attached".to_owned());
+    }
+
+    if has(ShaderType::Compute) {
+        return match stages.iter().all(|&t| t == ShaderType::Compute) {
+            true => Ok(()),
+            false => Err("compute shaders cannot be linked with graphics stages".to_owned()),
+        };
+    }
 
-pub use uniform;
+    if !has(ShaderType::Vertex) {
+        return Err("graphics programs need a vertex shader".to_owned());
+    }
+
+    if has(ShaderType::TessControl) && !has(ShaderType::TessEvaluation) {
+        return Err("tessellation control shader without an evaluation shader".to_owned());
+    }
+
+    Ok(())
+}
 
 // true == shader, false == program
-fn check_errors(id: u32, t: bool) {
+// returns the full info log when compilation/linking failed
+fn check_errors(id: u32, t: bool) -> std::result::Result<(), String> {
     let mut success: i32 = i32::from(gl::FALSE);
-    let mut info_log = vec![0u8; 512];
+    let mut length = 0;
 
     unsafe {
         if t {
@@ -174,30 +807,250 @@ fn check_errors(id: u32, t: bool) {
             gl::GetProgramiv(id, gl::LINK_STATUS, &mut success);
         }
 
-        if success != i32::from(gl::TRUE) {
-            if t {
-                gl::GetShaderInfoLog(
-                    id,
-                    512,
-                    ptr::null_mut(),
-                    info_log.as_mut_ptr() as *mut GLchar,
-                );
-                println!(
-                    "ERROR::SHADER::COMPILATION_ERROR\n {}",
-                    std::string::String::from_utf8_lossy(&info_log)
-                );
-            } else {
-                gl::GetProgramInfoLog(
-                    id,
-                    512,
-                    ptr::null_mut(),
-                    info_log.as_mut_ptr() as *mut GLchar,
-                );
-                println!(
-                    "ERROR::PROGRAM::LINKING_ERROR\n {}",
-                    std::string::String::from_utf8_lossy(&info_log)
-                );
-            }
+        if success == i32::from(gl::TRUE) {
+            return Ok(());
+        }
+
+        // includes the nul terminator
+        if t {
+            gl::GetShaderiv(id, gl::INFO_LOG_LENGTH, &mut length);
+        } else {
+            gl::GetProgramiv(id, gl::INFO_LOG_LENGTH, &mut length);
         }
     }
+
+    let mut info_log = vec![0u8; length.max(1) as usize];
+    let mut written = 0;
+
+    unsafe {
+        if t {
+            gl::GetShaderInfoLog(
+                id,
+                info_log.len() as i32,
+                &mut written,
+                info_log.as_mut_ptr() as *mut GLchar,
+            );
+        } else {
+            gl::GetProgramInfoLog(
+                id,
+                info_log.len() as i32,
+                &mut written,
+                info_log.as_mut_ptr() as *mut GLchar,
+            );
+        }
+    }
+
+    info_log.truncate(written as usize);
+
+    Err(String::from_utf8_lossy(&info_log).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a directory under the system temp dir, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl std::ops::Deref for TempDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    // a fresh directory holding `files`
+    fn write_files(test: &str, files: &[(&str, &str)]) -> TempDir {
+        let dir = std::env::temp_dir().join(format!(
+            "wrapper-preprocessor-{}-{test}",
+            std::process::id()
+        ));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        for (name, source) in files {
+            fs::write(dir.join(name), source).unwrap();
+        }
+
+        TempDir(dir)
+    }
+
+    #[test]
+    fn include() {
+        let dir = write_files(
+            "include",
+            &[
+                (
+                    "main.frag",
+                    "#version 450\n#include \"common.glsl\"\nvoid main() {}\n",
+                ),
+                ("common.glsl", "float one() { return 1.; }\n"),
+            ],
+        );
+
+        let processed = Preprocessor::new()
+            .process_file(dir.join("main.frag"))
+            .unwrap();
+
+        assert_eq!(
+            processed.source,
+            "#version 450\n\
+             #line 1 1\n\
+             float one() { return 1.; }\n\
+             #line 3 0\n\
+             void main() {}\n"
+        );
+        assert_eq!(
+            processed.files,
+            [dir.join("main.frag"), dir.join("common.glsl")]
+        );
+    }
+
+    #[test]
+    fn pragma_once() {
+        let dir = write_files(
+            "pragma-once",
+            &[
+                (
+                    "main.frag",
+                    "#include \"a.glsl\"\n#include \"b.glsl\"\n#include \"a.glsl\"\n",
+                ),
+                ("a.glsl", "#pragma once\nint a;\n"),
+                ("b.glsl", "#include \"a.glsl\"\nint b;\n"),
+            ],
+        );
+
+        let processed = Preprocessor::new()
+            .process_file(dir.join("main.frag"))
+            .unwrap();
+
+        assert_eq!(processed.source.matches("int a;").count(), 1);
+        assert_eq!(processed.files.len(), 3);
+    }
+
+    #[test]
+    fn include_guard() {
+        let guarded = "// lighting\n#ifndef LIGHTING\n#define LIGHTING\nint light;\n#endif\n";
+        let dir = write_files(
+            "include-guard",
+            &[
+                (
+                    "main.frag",
+                    "#include \"lighting.glsl\"\n#include \"lighting.glsl\"\n",
+                ),
+                ("lighting.glsl", guarded),
+            ],
+        );
+
+        let processed = Preprocessor::new()
+            .process_file(dir.join("main.frag"))
+            .unwrap();
+
+        assert_eq!(processed.source.matches("int light;").count(), 1);
+        assert_eq!(processed.files.len(), 2);
+    }
+
+    #[test]
+    fn include_cycle() {
+        let dir = write_files(
+            "include-cycle",
+            &[
+                ("a.glsl", "#include \"b.glsl\"\n"),
+                ("b.glsl", "#include \"a.glsl\"\n"),
+            ],
+        );
+
+        let Err(Error::IncludeCycle(cycle)) = Preprocessor::new().process_file(dir.join("a.glsl"))
+        else {
+            panic!("expected an include cycle");
+        };
+
+        let a = canonical(&dir.join("a.glsl"));
+        let b = canonical(&dir.join("b.glsl"));
+        assert_eq!(cycle, [a.clone(), b, a]);
+    }
+
+    #[test]
+    fn missing_include_reports_the_line() {
+        let dir = write_files(
+            "missing-include",
+            &[("main.frag", "#version 450\n\n#include \"missing.glsl\"\n")],
+        );
+
+        let Err(Error::Preprocess { file, line, .. }) =
+            Preprocessor::new().process_file(dir.join("main.frag"))
+        else {
+            panic!("expected a preprocess error");
+        };
+
+        assert_eq!(file, dir.join("main.frag"));
+        assert_eq!(line, 3);
+    }
+
+    #[test]
+    fn defines_follow_the_version() {
+        let dir = write_files(
+            "defines",
+            &[
+                ("main.frag", "// header\n#version 450\nvoid main() {}\n"),
+                ("plain.frag", "void main() {}\n"),
+            ],
+        );
+        let preprocessor = Preprocessor::new().define("LIGHTS", "4");
+
+        let processed = preprocessor.process_file(dir.join("main.frag")).unwrap();
+        assert_eq!(
+            processed.source,
+            "// header\n\
+             #version 450\n\
+             #define LIGHTS 4\n\
+             #line 3 0\n\
+             void main() {}\n"
+        );
+
+        // without a version they go first
+        let processed = preprocessor.process_file(dir.join("plain.frag")).unwrap();
+        assert_eq!(
+            processed.source,
+            "#define LIGHTS 4\n\
+             #line 1 0\n\
+             void main() {}\n"
+        );
+    }
+
+    #[test]
+    fn included_version_is_dropped() {
+        let dir = write_files(
+            "included-version",
+            &[
+                ("main.frag", "#version 450\n#include \"lib.glsl\"\n"),
+                ("lib.glsl", "#version 450\nint x;\n"),
+            ],
+        );
+
+        let processed = Preprocessor::new()
+            .define("X", "1")
+            .process_file(dir.join("main.frag"))
+            .unwrap();
+
+        // the blank line keeps the included file's line numbers right
+        assert_eq!(
+            processed.source,
+            "#version 450\n\
+             #define X 1\n\
+             #line 2 0\n\
+             #line 1 1\n\
+             \n\
+             int x;\n\
+             #line 3 0\n"
+        );
+    }
 }
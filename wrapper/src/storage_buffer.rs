@@ -0,0 +1,105 @@
+use std::marker::PhantomData;
+
+use crate::{
+    buffer::{Binding, Buffer, BufferType, DrawType, SHADER_STORAGE_BINDINGS},
+    error::Result,
+    layout::Std430,
+    shader_program::ShaderProgram,
+};
+
+/// A shader storage buffer holding an array of `T` in `std430` layout, matching
+/// a block like `buffer Particles { Particle particles[]; };`.
+///
+/// Call [`ShaderProgram::memory_barrier`] with `Barrier::BufferUpdate` after
+/// a dispatch that writes the buffer and before [`StorageBuffer::read`].
+pub struct StorageBuffer<T: Std430> {
+    buffer: Buffer,
+    binding: Binding,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Std430> StorageBuffer<T> {
+    /// Creates the buffer on the lowest binding point not used by another
+    /// `StorageBuffer`.
+    pub fn new(values: &[T]) -> Self {
+        Self::create(Binding::acquire(&SHADER_STORAGE_BINDINGS), values)
+    }
+
+    /// Creates the buffer on a fixed binding point, e.g. one declared in GLSL
+    /// with `layout(binding = N)`. Fails if another `StorageBuffer` uses it.
+    pub fn with_binding(binding: u32, values: &[T]) -> Result<Self> {
+        Ok(Self::create(
+            Binding::reserve(&SHADER_STORAGE_BINDINGS, binding)?,
+            values,
+        ))
+    }
+
+    fn create(binding: Binding, values: &[T]) -> Self {
+        let mut buffer = Buffer::new(BufferType::ShaderStorage);
+        // never given immutable storage, and storage buffers are indexed, so
+        // neither call can fail
+        buffer
+            .data_bytes(&to_bytes(values), DrawType::DynamicDraw)
+            .unwrap();
+        buffer.bind_base(binding.index()).unwrap();
+
+        Self {
+            buffer,
+            binding,
+            len: values.len(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn binding(&self) -> u32 {
+        self.binding.index()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Replaces the contents, reallocating if the number of elements changed.
+    pub fn set(&mut self, values: &[T]) {
+        let bytes = to_bytes(values);
+
+        if values.len() == self.len {
+            self.buffer.sub_data_bytes(0, &bytes);
+        } else {
+            self.buffer
+                .data_bytes(&bytes, DrawType::DynamicDraw)
+                .unwrap();
+            self.buffer.bind_base(self.binding()).unwrap();
+            self.len = values.len();
+        }
+    }
+
+    /// Copies the contents back from the GPU.
+    pub fn read(&self) -> Vec<T> {
+        self.buffer
+            .read_bytes(0, self.len * T::STRIDE)
+            .chunks_exact(T::STRIDE)
+            .map(|element| T::read_std430(&element[..T::SIZE]))
+            .collect()
+    }
+
+    /// Connects the storage block `name` of `program` to this buffer.
+    pub fn attach(&self, program: &ShaderProgram, name: &str) -> Result<()> {
+        program.bind_storage_block(name, self.binding())
+    }
+}
+
+fn to_bytes<T: Std430>(values: &[T]) -> Vec<u8> {
+    let mut bytes = vec![0; values.len() * T::STRIDE];
+
+    for (value, out) in values.iter().zip(bytes.chunks_exact_mut(T::STRIDE)) {
+        value.write_std430(&mut out[..T::SIZE]);
+    }
+
+    bytes
+}
@@ -1,19 +1,274 @@
-use std::{ffi::c_void, path::Path};
+use std::{
+    f32::consts::{PI, TAU},
+    ffi::c_void,
+    path::Path,
+};
 
 use gl::types::GLenum;
 
-use image::{io::Reader, DynamicImage};
+use bytemuck::Pod;
+use image::{DynamicImage, ImageReader, Rgb32FImage};
+use nalgebra_glm as glm;
 
+use crate::{
+    error::{Error, Result},
+    sampler::{SamplerDesc, Wrap},
+    vertex_array::GlType,
+};
+
+/// Layout of the pixel data uploaded to a texture.
+///
+/// Packed depth-stencil data can't be uploaded, create those textures
+/// [`empty`](Texture::empty) and render into them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TextureFormat {
+    Red,
+    Rg,
     Rgb,
     Rgba,
+    Bgr,
+    Bgra,
+    DepthComponent,
 }
 
 impl TextureFormat {
     pub fn resolve(&self) -> GLenum {
         match self {
+            Self::Red => gl::RED,
+            Self::Rg => gl::RG,
             Self::Rgb => gl::RGB,
             Self::Rgba => gl::RGBA,
+            Self::Bgr => gl::BGR,
+            Self::Bgra => gl::BGRA,
+            Self::DepthComponent => gl::DEPTH_COMPONENT,
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        match self {
+            Self::Red | Self::DepthComponent => 1,
+            Self::Rg => 2,
+            Self::Rgb | Self::Bgr => 3,
+            Self::Rgba | Self::Bgra => 4,
+        }
+    }
+}
+
+/// How a texture stores its texels on the GPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InternalFormat {
+    R8,
+    Rg8,
+    Rgb8,
+    Rgba8,
+    R16,
+    Rg16,
+    Rgb16,
+    Rgba16,
+    /// sRGB color, converted to linear when sampled.
+    Srgb8,
+    Srgb8Alpha8,
+    R16F,
+    Rg16F,
+    Rgb16F,
+    Rgba16F,
+    R32F,
+    Rgb32F,
+    Rgba32F,
+    DepthComponent16,
+    DepthComponent24,
+    DepthComponent32F,
+    Depth24Stencil8,
+    Depth32FStencil8,
+}
+
+impl InternalFormat {
+    pub fn resolve(&self) -> GLenum {
+        match self {
+            Self::R8 => gl::R8,
+            Self::Rg8 => gl::RG8,
+            Self::Rgb8 => gl::RGB8,
+            Self::Rgba8 => gl::RGBA8,
+            Self::R16 => gl::R16,
+            Self::Rg16 => gl::RG16,
+            Self::Rgb16 => gl::RGB16,
+            Self::Rgba16 => gl::RGBA16,
+            Self::Srgb8 => gl::SRGB8,
+            Self::Srgb8Alpha8 => gl::SRGB8_ALPHA8,
+            Self::R16F => gl::R16F,
+            Self::Rg16F => gl::RG16F,
+            Self::Rgb16F => gl::RGB16F,
+            Self::Rgba16F => gl::RGBA16F,
+            Self::R32F => gl::R32F,
+            Self::Rgb32F => gl::RGB32F,
+            Self::Rgba32F => gl::RGBA32F,
+            Self::DepthComponent16 => gl::DEPTH_COMPONENT16,
+            Self::DepthComponent24 => gl::DEPTH_COMPONENT24,
+            Self::DepthComponent32F => gl::DEPTH_COMPONENT32F,
+            Self::Depth24Stencil8 => gl::DEPTH24_STENCIL8,
+            Self::Depth32FStencil8 => gl::DEPTH32F_STENCIL8,
+        }
+    }
+
+    pub fn is_depth(&self) -> bool {
+        matches!(
+            self,
+            Self::DepthComponent16
+                | Self::DepthComponent24
+                | Self::DepthComponent32F
+                | Self::Depth24Stencil8
+                | Self::Depth32FStencil8
+        )
+    }
+
+    // a format and type that are valid to allocate the texture without data
+    fn default_upload(&self) -> (GLenum, GLenum) {
+        match self {
+            Self::Depth24Stencil8 => (gl::DEPTH_STENCIL, gl::UNSIGNED_INT_24_8),
+            Self::Depth32FStencil8 => (gl::DEPTH_STENCIL, gl::FLOAT_32_UNSIGNED_INT_24_8_REV),
+            Self::DepthComponent16 | Self::DepthComponent24 | Self::DepthComponent32F => {
+                (gl::DEPTH_COMPONENT, gl::FLOAT)
+            }
+            _ => (gl::RGBA, gl::UNSIGNED_BYTE),
+        }
+    }
+}
+
+/// The kind of a texture, which decides the target it is bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureTarget {
+    Texture1D,
+    Texture2D,
+    Texture3D,
+    Texture2DArray,
+    CubeMap,
+    Texture2DMultisample,
+}
+
+impl TextureTarget {
+    pub fn resolve(&self) -> GLenum {
+        match self {
+            Self::Texture1D => gl::TEXTURE_1D,
+            Self::Texture2D => gl::TEXTURE_2D,
+            Self::Texture3D => gl::TEXTURE_3D,
+            Self::Texture2DArray => gl::TEXTURE_2D_ARRAY,
+            Self::CubeMap => gl::TEXTURE_CUBE_MAP,
+            Self::Texture2DMultisample => gl::TEXTURE_2D_MULTISAMPLE,
+        }
+    }
+}
+
+/// Kind, size, format and sampling of a texture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureDesc {
+    pub target: TextureTarget,
+    pub width: u32,
+    /// 1 for 1D textures.
+    pub height: u32,
+    /// Depth of 3D textures and layers of arrays, 1 for the others.
+    pub depth: u32,
+    /// Samples per texel of multisample textures.
+    pub samples: u32,
+    pub internal_format: InternalFormat,
+    /// Generate mipmaps after uploading. Without them the sampler's mipmap
+    /// filter is ignored.
+    pub mipmaps: bool,
+    pub sampler: SamplerDesc,
+}
+
+impl TextureDesc {
+    /// A 2D texture with mipmaps, unless it has a depth format.
+    pub fn new(width: u32, height: u32, internal_format: InternalFormat) -> Self {
+        Self::with_target(
+            TextureTarget::Texture2D,
+            [width, height, 1],
+            internal_format,
+        )
+    }
+
+    pub fn new_1d(width: u32, internal_format: InternalFormat) -> Self {
+        Self::with_target(TextureTarget::Texture1D, [width, 1, 1], internal_format)
+    }
+
+    pub fn new_3d(width: u32, height: u32, depth: u32, internal_format: InternalFormat) -> Self {
+        Self::with_target(
+            TextureTarget::Texture3D,
+            [width, height, depth],
+            internal_format,
+        )
+    }
+
+    pub fn new_array(
+        width: u32,
+        height: u32,
+        layers: u32,
+        internal_format: InternalFormat,
+    ) -> Self {
+        Self::with_target(
+            TextureTarget::Texture2DArray,
+            [width, height, layers],
+            internal_format,
+        )
+    }
+
+    /// A cube map with square faces, clamped at the edges. Filtering only
+    /// crosses face edges with [`Texture::seamless_cube_maps`].
+    pub fn cube_map(size: u32, internal_format: InternalFormat) -> Self {
+        let desc = Self::with_target(TextureTarget::CubeMap, [size, size, 1], internal_format);
+        desc.sampler(desc.sampler.wrap(Wrap::ClampToEdge))
+    }
+
+    /// A multisample texture, which can only be rendered into and fetched
+    /// from, so it has neither mipmaps nor sampler state.
+    pub fn multisample(
+        width: u32,
+        height: u32,
+        samples: u32,
+        internal_format: InternalFormat,
+    ) -> Self {
+        Self {
+            samples,
+            mipmaps: false,
+            ..Self::with_target(
+                TextureTarget::Texture2DMultisample,
+                [width, height, 1],
+                internal_format,
+            )
+        }
+    }
+
+    fn with_target(
+        target: TextureTarget,
+        [width, height, depth]: [u32; 3],
+        internal_format: InternalFormat,
+    ) -> Self {
+        Self {
+            target,
+            width,
+            height,
+            depth,
+            samples: 0,
+            internal_format,
+            mipmaps: !internal_format.is_depth(),
+            sampler: SamplerDesc::default(),
+        }
+    }
+
+    pub fn mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    pub fn sampler(mut self, sampler: SamplerDesc) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    // 2D images making up the texture: layers, slices or cube faces
+    fn images(&self) -> usize {
+        match self.target {
+            TextureTarget::CubeMap => 6,
+            _ => self.depth as usize,
         }
     }
 }
@@ -21,69 +276,475 @@ impl TextureFormat {
 pub struct Texture {
     id: u32,
     number: i32,
+    desc: TextureDesc,
 }
 
 impl Texture {
-    pub fn new(data: Vec<u8>, height: u32, width: u32, format: TextureFormat) -> Self {
-        let mut texture = Texture { id: 0, number: -1 };
+    /// Creates a texture from rows of pixels, bottom row first, laid out as
+    /// `format` with components of type `T`. Layers, slices and cube faces
+    /// (in the order +X, -X, +Y, -Y, +Z, -Z) follow each other.
+    ///
+    /// Panics for multisample textures, which have to be created
+    /// [`empty`](Texture::empty).
+    pub fn new<T: GlType + Pod>(
+        desc: TextureDesc,
+        format: TextureFormat,
+        data: &[T],
+    ) -> Result<Self> {
+        assert!(
+            desc.target != TextureTarget::Texture2DMultisample,
+            "multisample textures can't be uploaded to"
+        );
+
+        let row = desc.width as usize * format.channels() * std::mem::size_of::<T>();
+        let expected = row * desc.height as usize * desc.images();
+        let actual = std::mem::size_of_val(data);
+
+        if actual != expected {
+            return Err(Error::TextureDataSize { expected, actual });
+        }
+
+        let texture = Self::create(desc);
+
+        unsafe {
+            // rows are tightly packed, so only as aligned as their size
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, row_alignment(row));
+        }
+
+        texture.allocate(
+            format.resolve(),
+            T::resolve(),
+            Some(bytemuck::cast_slice(data)),
+        );
+
+        unsafe {
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        }
+
+        texture.generate_mipmaps();
+
+        Ok(texture)
+    }
+
+    /// Allocates a texture without initializing it, e.g. to render into.
+    pub fn empty(desc: TextureDesc) -> Self {
+        let texture = Self::create(desc);
+        let (format, gl_type) = desc.internal_format.default_upload();
+
+        texture.allocate(format, gl_type, None);
+        texture.generate_mipmaps();
+
+        texture
+    }
+
+    fn create(desc: TextureDesc) -> Self {
+        let mut texture = Texture {
+            id: 0,
+            number: -1,
+            desc,
+        };
 
         unsafe {
             gl::GenTextures(1, &mut texture.id);
-            texture.bind();
-
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
-            gl::TexParameteri(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_MIN_FILTER,
-                gl::LINEAR_MIPMAP_LINEAR as i32,
-            );
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
-
-            gl::TexImage2D(
-                gl::TEXTURE_2D,
-                0,
-                gl::RGB as i32,
-                height as i32,
-                width as i32,
-                0,
-                format.resolve(),
-                gl::UNSIGNED_BYTE,
-                &data[0] as *const u8 as *const c_void,
-            );
-            gl::GenerateMipmap(gl::TEXTURE_2D);
+        }
+
+        texture.bind();
+
+        // multisample textures reject sampler parameters
+        if desc.target != TextureTarget::Texture2DMultisample {
+            texture.apply_sampler();
         }
 
         texture
     }
 
-    pub fn from_file(path: &str) -> Self {
-        let img = Reader::open(Path::new(path))
-            .unwrap()
-            .decode()
-            .unwrap()
-            .fliph()
-            .flipv();
+    // defines the base level of the bound texture, from `data` if any
+    fn allocate(&self, format: GLenum, gl_type: GLenum, data: Option<&[u8]>) {
+        let desc = &self.desc;
+        let target = desc.target.resolve();
+        let internal_format = desc.internal_format.resolve() as i32;
+        let (width, height, depth) = (desc.width as i32, desc.height as i32, desc.depth as i32);
+
+        let pixels = |offset: usize| match data {
+            Some(data) => data[offset..].as_ptr() as *const c_void,
+            None => std::ptr::null(),
+        };
+
+        unsafe {
+            match desc.target {
+                TextureTarget::Texture1D => gl::TexImage1D(
+                    target,
+                    0,
+                    internal_format,
+                    width,
+                    0,
+                    format,
+                    gl_type,
+                    pixels(0),
+                ),
+                TextureTarget::Texture2D => gl::TexImage2D(
+                    target,
+                    0,
+                    internal_format,
+                    width,
+                    height,
+                    0,
+                    format,
+                    gl_type,
+                    pixels(0),
+                ),
+                TextureTarget::Texture3D | TextureTarget::Texture2DArray => gl::TexImage3D(
+                    target,
+                    0,
+                    internal_format,
+                    width,
+                    height,
+                    depth,
+                    0,
+                    format,
+                    gl_type,
+                    pixels(0),
+                ),
+                TextureTarget::CubeMap => {
+                    let face_size = data.map_or(0, |data| data.len() / 6);
+
+                    for face in 0..6 {
+                        gl::TexImage2D(
+                            gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as u32,
+                            0,
+                            internal_format,
+                            width,
+                            height,
+                            0,
+                            format,
+                            gl_type,
+                            pixels(face * face_size),
+                        );
+                    }
+                }
+                TextureTarget::Texture2DMultisample => gl::TexImage2DMultisample(
+                    target,
+                    desc.samples as i32,
+                    internal_format as GLenum,
+                    width,
+                    height,
+                    gl::TRUE,
+                ),
+            }
+        }
+    }
+
+    /// Replaces one layer of an array, slice of a 3D texture or face of a
+    /// cube map, and regenerates the mipmaps.
+    ///
+    /// Panics if there is no such layer or the texture is multisampled.
+    pub fn set_layer<T: GlType + Pod>(
+        &self,
+        layer: u32,
+        format: TextureFormat,
+        data: &[T],
+    ) -> Result<()> {
+        let desc = &self.desc;
+
+        assert!(
+            desc.target != TextureTarget::Texture2DMultisample,
+            "multisample textures can't be uploaded to"
+        );
+        assert!(
+            (layer as usize) < desc.images(),
+            "layer {layer} out of range of {} layers",
+            desc.images()
+        );
+
+        let row = desc.width as usize * format.channels() * std::mem::size_of::<T>();
+        let expected = row * desc.height as usize;
+        let actual = std::mem::size_of_val(data);
+
+        if actual != expected {
+            return Err(Error::TextureDataSize { expected, actual });
+        }
+
+        self.bind();
+
+        let (width, height) = (desc.width as i32, desc.height as i32);
+        let pixels = data.as_ptr() as *const c_void;
+
+        unsafe {
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, row_alignment(row));
+
+            match desc.target {
+                TextureTarget::Texture1D => gl::TexSubImage1D(
+                    gl::TEXTURE_1D,
+                    0,
+                    0,
+                    width,
+                    format.resolve(),
+                    T::resolve(),
+                    pixels,
+                ),
+                TextureTarget::Texture2D => gl::TexSubImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    0,
+                    0,
+                    width,
+                    height,
+                    format.resolve(),
+                    T::resolve(),
+                    pixels,
+                ),
+                TextureTarget::Texture3D | TextureTarget::Texture2DArray => gl::TexSubImage3D(
+                    desc.target.resolve(),
+                    0,
+                    0,
+                    0,
+                    layer as i32,
+                    width,
+                    height,
+                    1,
+                    format.resolve(),
+                    T::resolve(),
+                    pixels,
+                ),
+                TextureTarget::CubeMap => gl::TexSubImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + layer,
+                    0,
+                    0,
+                    0,
+                    width,
+                    height,
+                    format.resolve(),
+                    T::resolve(),
+                    pixels,
+                ),
+                TextureTarget::Texture2DMultisample => unreachable!(),
+            }
+
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        }
+
+        self.generate_mipmaps();
+
+        Ok(())
+    }
+
+    fn generate_mipmaps(&self) {
+        if self.desc.mipmaps {
+            unsafe {
+                gl::GenerateMipmap(self.desc.target.resolve());
+            }
+        }
+    }
+
+    /// Loads a 2D array with one image per layer, all of the same size.
+    /// They are stored as 8 bit RGBA, or as float RGB if any is a float
+    /// image.
+    pub fn array_from_files<P: AsRef<Path>>(paths: &[P], flip_vertically: bool) -> Result<Self> {
+        let (width, height, layers) = load_layers(paths, flip_vertically, false)?;
+        let count = paths.len() as u32;
+
+        match layers {
+            Layers::Rgba8(data) => Self::new(
+                TextureDesc::new_array(width, height, count, InternalFormat::Rgba8),
+                TextureFormat::Rgba,
+                &data,
+            ),
+            Layers::Rgb32F(data) => Self::new(
+                TextureDesc::new_array(width, height, count, InternalFormat::Rgb32F),
+                TextureFormat::Rgb,
+                &data,
+            ),
+        }
+    }
+
+    /// Loads a cube map from square images of its faces, in the order +X,
+    /// -X, +Y, -Y, +Z, -Z. Cube map faces start at the top row, so they
+    /// are not flipped. Stored like [`Texture::array_from_files`] does.
+    pub fn cube_map_from_files<P: AsRef<Path>>(paths: &[P; 6]) -> Result<Self> {
+        let (size, _, faces) = load_layers(paths, false, true)?;
+
+        match faces {
+            Layers::Rgba8(data) => Self::new(
+                TextureDesc::cube_map(size, InternalFormat::Rgba8),
+                TextureFormat::Rgba,
+                &data,
+            ),
+            Layers::Rgb32F(data) => Self::new(
+                TextureDesc::cube_map(size, InternalFormat::Rgb32F),
+                TextureFormat::Rgb,
+                &data,
+            ),
+        }
+    }
+
+    /// Loads an equirectangular (latitude-longitude) image, usually an
+    /// `.hdr` environment map, and resamples it into a cube map with faces
+    /// of `size` texels, stored as float RGB. The middle of the image ends
+    /// up at +X.
+    pub fn cube_map_from_equirectangular(path: impl AsRef<Path>, size: u32) -> Result<Self> {
+        let img = ImageReader::open(path)?.decode()?.into_rgb32f();
 
         Self::new(
-            img.as_bytes().to_vec(),
-            img.height(),
-            img.width(),
-            match img {
-                DynamicImage::ImageRgb8(_) => TextureFormat::Rgb,
-                DynamicImage::ImageRgba8(_) => TextureFormat::Rgba,
-                _ => panic!("Ty kurwo jebana"),
-            },
+            TextureDesc::cube_map(size, InternalFormat::Rgb32F),
+            TextureFormat::Rgb,
+            &equirectangular_to_cube(&img, size),
         )
     }
 
+    /// Loads an image, flipped so its top row ends up at `v = 1` as OpenGL
+    /// expects. `.hdr` and `.exr` files are kept as floats.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_file_with(path, true)
+    }
+
+    /// Loads an image, flipping it vertically or not.
+    pub fn from_file_with(path: impl AsRef<Path>, flip_vertically: bool) -> Result<Self> {
+        let img = ImageReader::open(path)?.decode()?;
+
+        match flip_vertically {
+            true => Self::from_image(&img.flipv()),
+            false => Self::from_image(&img),
+        }
+    }
+
+    /// Uploads a decoded image as is, in the internal format closest to its
+    /// own. Grey images are stored in the red channel and read back as grey.
+    pub fn from_image(img: &DynamicImage) -> Result<Self> {
+        let desc = |internal_format| TextureDesc::new(img.width(), img.height(), internal_format);
+
+        let texture = match img {
+            DynamicImage::ImageLuma8(img) => {
+                Self::new(desc(InternalFormat::R8), TextureFormat::Red, img.as_raw())?
+            }
+            DynamicImage::ImageLumaA8(img) => {
+                Self::new(desc(InternalFormat::Rg8), TextureFormat::Rg, img.as_raw())?
+            }
+            DynamicImage::ImageRgb8(img) => {
+                Self::new(desc(InternalFormat::Rgb8), TextureFormat::Rgb, img.as_raw())?
+            }
+            DynamicImage::ImageRgba8(img) => Self::new(
+                desc(InternalFormat::Rgba8),
+                TextureFormat::Rgba,
+                img.as_raw(),
+            )?,
+            DynamicImage::ImageLuma16(img) => {
+                Self::new(desc(InternalFormat::R16), TextureFormat::Red, img.as_raw())?
+            }
+            DynamicImage::ImageLumaA16(img) => {
+                Self::new(desc(InternalFormat::Rg16), TextureFormat::Rg, img.as_raw())?
+            }
+            DynamicImage::ImageRgb16(img) => Self::new(
+                desc(InternalFormat::Rgb16),
+                TextureFormat::Rgb,
+                img.as_raw(),
+            )?,
+            DynamicImage::ImageRgba16(img) => Self::new(
+                desc(InternalFormat::Rgba16),
+                TextureFormat::Rgba,
+                img.as_raw(),
+            )?,
+            DynamicImage::ImageRgb32F(img) => Self::new(
+                desc(InternalFormat::Rgb32F),
+                TextureFormat::Rgb,
+                img.as_raw(),
+            )?,
+            DynamicImage::ImageRgba32F(img) => Self::new(
+                desc(InternalFormat::Rgba32F),
+                TextureFormat::Rgba,
+                img.as_raw(),
+            )?,
+            // formats added to `image` later
+            img => Self::new(
+                desc(InternalFormat::Rgba8),
+                TextureFormat::Rgba,
+                img.to_rgba8().as_raw(),
+            )?,
+        };
+
+        match img.color().channel_count() {
+            1 => texture.set_swizzle([gl::RED, gl::RED, gl::RED, gl::ONE]),
+            2 => texture.set_swizzle([gl::RED, gl::RED, gl::RED, gl::GREEN]),
+            _ => {}
+        }
+
+        Ok(texture)
+    }
+
+    pub fn desc(&self) -> &TextureDesc {
+        &self.desc
+    }
+
+    pub fn width(&self) -> u32 {
+        self.desc.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.desc.height
+    }
+
+    pub fn target(&self) -> TextureTarget {
+        self.desc.target
+    }
+
     pub fn bind(&self) {
         unsafe {
             if self.number > -1 {
                 gl::ActiveTexture(gl::TEXTURE0 + self.number as u32);
             }
 
-            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::BindTexture(self.desc.target.resolve(), self.id);
+        }
+    }
+
+    /// Binds the texture to texture unit `unit`, which stays active.
+    pub fn bind_to(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(self.desc.target.resolve(), self.id);
+        }
+    }
+
+    /// Changes how the texture is sampled when no [`Sampler`] is bound.
+    ///
+    /// [`Sampler`]: crate::sampler::Sampler
+    pub fn set_sampler(&mut self, sampler: SamplerDesc) {
+        self.desc.sampler = sampler;
+        self.apply_sampler();
+    }
+
+    fn apply_sampler(&self) {
+        let mut sampler = self.desc.sampler;
+
+        // sampling missing mipmaps would leave the texture incomplete
+        if !self.desc.mipmaps {
+            sampler.mipmap_filter = None;
+        }
+
+        self.bind();
+
+        let target = self.desc.target.resolve();
+
+        sampler.apply(
+            |name, value| unsafe { gl::TexParameteri(target, name, value) },
+            |name, value| unsafe { gl::TexParameterf(target, name, value) },
+            |name, values| unsafe { gl::TexParameterfv(target, name, values.as_ptr()) },
+        );
+    }
+
+    /// Sets where the red, green, blue and alpha components read from, each
+    /// one of `GL_RED`, `GL_GREEN`, `GL_BLUE`, `GL_ALPHA`, `GL_ZERO` or
+    /// `GL_ONE`.
+    pub fn set_swizzle(&self, swizzle: [GLenum; 4]) {
+        self.bind();
+
+        let swizzle = swizzle.map(|component| component as i32);
+
+        unsafe {
+            gl::TexParameteriv(
+                self.desc.target.resolve(),
+                gl::TEXTURE_SWIZZLE_RGBA,
+                swizzle.as_ptr(),
+            );
         }
     }
 
@@ -96,4 +757,153 @@ impl Texture {
             gl::ActiveTexture(gl::TEXTURE0 + number);
         }
     }
+
+    /// Makes all cube maps of the context filter across face edges instead
+    /// of clamping at them. Usually enabled once after creating the context.
+    pub fn seamless_cube_maps(enabled: bool) {
+        unsafe {
+            match enabled {
+                true => gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS),
+                false => gl::Disable(gl::TEXTURE_CUBE_MAP_SEAMLESS),
+            }
+        }
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+fn row_alignment(row: usize) -> i32 {
+    [8, 4, 2]
+        .into_iter()
+        .find(|&a| row.is_multiple_of(a))
+        .unwrap_or(1) as i32
+}
+
+// images of the same size converted to one format, one after the other
+enum Layers {
+    Rgba8(Vec<u8>),
+    Rgb32F(Vec<f32>),
+}
+
+fn load_layers<P: AsRef<Path>>(
+    paths: &[P],
+    flip_vertically: bool,
+    square: bool,
+) -> Result<(u32, u32, Layers)> {
+    let mut images = Vec::with_capacity(paths.len());
+
+    for path in paths {
+        let img = ImageReader::open(path)?.decode()?;
+
+        let img = match flip_vertically {
+            true => img.flipv(),
+            false => img,
+        };
+
+        let expected = match (images.first(), square) {
+            (Some((_, first)), _) => Some(dimensions(first)),
+            (None, true) => Some((img.width(), img.width())),
+            (None, false) => None,
+        };
+
+        if let Some(expected) = expected.filter(|&expected| expected != dimensions(&img)) {
+            return Err(Error::ImageSize {
+                file: path.as_ref().to_owned(),
+                expected,
+                actual: dimensions(&img),
+            });
+        }
+
+        images.push((path, img));
+    }
+
+    let (width, height) = images.first().map_or((0, 0), |(_, img)| dimensions(img));
+    let float = images.iter().any(|(_, img)| {
+        matches!(
+            img,
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+        )
+    });
+
+    let layers = match float {
+        true => Layers::Rgb32F(
+            images
+                .iter()
+                .flat_map(|(_, img)| img.to_rgb32f().into_raw())
+                .collect(),
+        ),
+        false => Layers::Rgba8(
+            images
+                .iter()
+                .flat_map(|(_, img)| img.to_rgba8().into_raw())
+                .collect(),
+        ),
+    };
+
+    Ok((width, height, layers))
+}
+
+fn dimensions(img: &DynamicImage) -> (u32, u32) {
+    (img.width(), img.height())
+}
+
+// resamples a latitude-longitude image into the six faces of a cube map, in
+// the orientation OpenGL looks them up in
+fn equirectangular_to_cube(img: &Rgb32FImage, size: u32) -> Vec<f32> {
+    let mut texels = Vec::with_capacity(6 * size as usize * size as usize * 3);
+
+    for face in 0..6 {
+        for row in 0..size {
+            for column in 0..size {
+                // position on the face, from -1 to 1
+                let s = 2. * (column as f32 + 0.5) / size as f32 - 1.;
+                let t = 2. * (row as f32 + 0.5) / size as f32 - 1.;
+
+                let direction = match face {
+                    0 => glm::vec3(1., -t, -s),
+                    1 => glm::vec3(-1., -t, s),
+                    2 => glm::vec3(s, 1., t),
+                    3 => glm::vec3(s, -1., -t),
+                    4 => glm::vec3(s, -t, 1.),
+                    _ => glm::vec3(-s, -t, -1.),
+                }
+                .normalize();
+
+                let u = 0.5 + direction.z.atan2(direction.x) / TAU;
+                let v = 0.5 - direction.y.asin() / PI;
+
+                texels.extend(sample_bilinear(img, u, v));
+            }
+        }
+    }
+
+    texels
+}
+
+// wraps around horizontally and clamps vertically
+fn sample_bilinear(img: &Rgb32FImage, u: f32, v: f32) -> [f32; 3] {
+    let (width, height) = img.dimensions();
+
+    let x = u * width as f32 - 0.5;
+    let y = (v * height as f32 - 0.5).clamp(0., height as f32 - 1.);
+    let (fx, fy) = (x - x.floor(), y - y.floor());
+
+    let texel = |x: f32, y: f32| {
+        let x = (x as i64).rem_euclid(width as i64) as u32;
+        let y = (y as u32).min(height - 1);
+
+        glm::Vec3::from(img.get_pixel(x, y).0)
+    };
+
+    let (x, y) = (x.floor(), y.floor());
+    let top = glm::lerp(&texel(x, y), &texel(x + 1., y), fx);
+    let bottom = glm::lerp(&texel(x, y + 1.), &texel(x + 1., y + 1.), fx);
+
+    glm::lerp(&top, &bottom, fy).into()
 }
@@ -0,0 +1,98 @@
+use crate::{sampler::Sampler, shader_program::ShaderProgram, texture::Texture};
+
+/// Hands out texture units to the textures of a draw and points the
+/// program's sampler uniforms at them.
+///
+/// ```ignore
+/// program.use_program();
+/// units.bind_textures(&program, &[("albedo", &albedo), ("normals", &normals)]);
+/// mesh.draw(Primitive::Triangles);
+/// ```
+pub struct TextureUnits {
+    next: u32,
+    max: u32,
+}
+
+impl TextureUnits {
+    pub fn new() -> Self {
+        let mut max = 0;
+
+        unsafe {
+            gl::GetIntegerv(gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS, &mut max);
+        }
+
+        Self {
+            next: 0,
+            max: max as u32,
+        }
+    }
+
+    /// Binds `texture` to the next free unit and sets the sampler uniform
+    /// `name` of `program`, which has to be in use, to it. Returns the unit.
+    ///
+    /// Panics if all units are taken.
+    pub fn bind(&mut self, program: &ShaderProgram, name: &str, texture: &Texture) -> u32 {
+        let unit = self.allocate();
+
+        // a sampler left on the unit would override the texture's own state
+        Sampler::unbind(unit);
+        texture.bind_to(unit);
+        program.set_uniform(name, &(unit as i32));
+
+        unit
+    }
+
+    /// Like [`TextureUnits::bind`], but sampled with `sampler`.
+    pub fn bind_with_sampler(
+        &mut self,
+        program: &ShaderProgram,
+        name: &str,
+        texture: &Texture,
+        sampler: &Sampler,
+    ) -> u32 {
+        let unit = self.allocate();
+
+        sampler.bind(unit);
+        texture.bind_to(unit);
+        program.set_uniform(name, &(unit as i32));
+
+        unit
+    }
+
+    /// Frees all units, then binds `textures` by their sampler uniform names.
+    pub fn bind_textures(&mut self, program: &ShaderProgram, textures: &[(&str, &Texture)]) {
+        self.reset();
+
+        for (name, texture) in textures {
+            self.bind(program, name, texture);
+        }
+    }
+
+    /// Frees all units for the next draw. The textures stay bound until the
+    /// units are handed out again.
+    pub fn reset(&mut self) {
+        self.next = 0;
+    }
+
+    /// Units handed out since the last reset.
+    pub fn used(&self) -> u32 {
+        self.next
+    }
+
+    fn allocate(&mut self) -> u32 {
+        assert!(
+            self.next < self.max,
+            "all {} texture units are in use",
+            self.max
+        );
+
+        self.next += 1;
+        self.next - 1
+    }
+}
+
+impl Default for TextureUnits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,452 @@
+use std::{collections::HashMap, ffi::CString};
+
+use gl::types::{GLchar, GLenum};
+
+use nalgebra_glm as glm;
+
+use crate::error::{Error, Result};
+
+/// An active uniform as reported by the driver after linking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UniformInfo {
+    pub location: i32,
+    /// `GL_FLOAT_VEC3`, `GL_SAMPLER_2D`, ...
+    pub gl_type: GLenum,
+    /// Number of array elements from `location` on, 1 for non-arrays.
+    pub size: i32,
+}
+
+/// A value that can be written to a uniform with
+/// [`ShaderProgram::set_uniform`](crate::shader_program::ShaderProgram::set_uniform).
+///
+/// Implemented for scalars, glm vectors and matrices, and for slices, arrays and
+/// `Vec`s of those, which fill uniform arrays starting at the given element.
+/// Note that glm's `MatRxC` has R rows, so `glm::Mat3x4` is a GLSL `mat4x3`.
+pub trait Uniform {
+    /// Type the uniform has to be declared with in GLSL.
+    const GL_TYPE: GLenum;
+
+    /// Writes the value to `location` of the currently bound program.
+    fn upload(&self, location: i32);
+
+    /// Number of array elements written, 1 for single values.
+    fn elements(&self) -> usize {
+        1
+    }
+}
+
+/// Uniform types that can also be uploaded as arrays.
+pub trait UniformElement: Uniform + Sized {
+    fn upload_slice(location: i32, values: &[Self]);
+}
+
+impl<T: UniformElement> Uniform for [T] {
+    const GL_TYPE: GLenum = T::GL_TYPE;
+
+    fn upload(&self, location: i32) {
+        T::upload_slice(location, self);
+    }
+
+    fn elements(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: UniformElement, const N: usize> Uniform for [T; N] {
+    const GL_TYPE: GLenum = T::GL_TYPE;
+
+    fn upload(&self, location: i32) {
+        T::upload_slice(location, self);
+    }
+
+    fn elements(&self) -> usize {
+        N
+    }
+}
+
+impl<T: UniformElement> Uniform for Vec<T> {
+    const GL_TYPE: GLenum = T::GL_TYPE;
+
+    fn upload(&self, location: i32) {
+        T::upload_slice(location, self);
+    }
+
+    fn elements(&self) -> usize {
+        self.len()
+    }
+}
+
+// matrices pass `gl::FALSE` for the transpose argument
+macro_rules! impl_uniform {
+    ($t:ty, $gl_type:expr, $function:ident, $ptr_t:ty $(, $transpose:expr)?) => {
+        impl Uniform for $t {
+            const GL_TYPE: GLenum = $gl_type;
+
+            fn upload(&self, location: i32) {
+                Self::upload_slice(location, std::slice::from_ref(self));
+            }
+        }
+
+        impl UniformElement for $t {
+            fn upload_slice(location: i32, values: &[Self]) {
+                unsafe {
+                    gl::$function(
+                        location,
+                        values.len() as i32,
+                        $($transpose,)?
+                        values.as_ptr() as *const $ptr_t,
+                    );
+                }
+            }
+        }
+    };
+}
+
+impl_uniform!(f32, gl::FLOAT, Uniform1fv, f32);
+impl_uniform!(glm::Vec2, gl::FLOAT_VEC2, Uniform2fv, f32);
+impl_uniform!(glm::Vec3, gl::FLOAT_VEC3, Uniform3fv, f32);
+impl_uniform!(glm::Vec4, gl::FLOAT_VEC4, Uniform4fv, f32);
+
+impl_uniform!(f64, gl::DOUBLE, Uniform1dv, f64);
+impl_uniform!(glm::DVec2, gl::DOUBLE_VEC2, Uniform2dv, f64);
+impl_uniform!(glm::DVec3, gl::DOUBLE_VEC3, Uniform3dv, f64);
+impl_uniform!(glm::DVec4, gl::DOUBLE_VEC4, Uniform4dv, f64);
+
+impl_uniform!(i32, gl::INT, Uniform1iv, i32);
+impl_uniform!(glm::IVec2, gl::INT_VEC2, Uniform2iv, i32);
+impl_uniform!(glm::IVec3, gl::INT_VEC3, Uniform3iv, i32);
+impl_uniform!(glm::IVec4, gl::INT_VEC4, Uniform4iv, i32);
+
+impl_uniform!(u32, gl::UNSIGNED_INT, Uniform1uiv, u32);
+impl_uniform!(glm::UVec2, gl::UNSIGNED_INT_VEC2, Uniform2uiv, u32);
+impl_uniform!(glm::UVec3, gl::UNSIGNED_INT_VEC3, Uniform3uiv, u32);
+impl_uniform!(glm::UVec4, gl::UNSIGNED_INT_VEC4, Uniform4uiv, u32);
+
+impl_uniform!(glm::Mat2, gl::FLOAT_MAT2, UniformMatrix2fv, f32, gl::FALSE);
+impl_uniform!(glm::Mat3, gl::FLOAT_MAT3, UniformMatrix3fv, f32, gl::FALSE);
+impl_uniform!(glm::Mat4, gl::FLOAT_MAT4, UniformMatrix4fv, f32, gl::FALSE);
+impl_uniform!(
+    glm::Mat2x3,
+    gl::FLOAT_MAT3x2,
+    UniformMatrix3x2fv,
+    f32,
+    gl::FALSE
+);
+impl_uniform!(
+    glm::Mat2x4,
+    gl::FLOAT_MAT4x2,
+    UniformMatrix4x2fv,
+    f32,
+    gl::FALSE
+);
+impl_uniform!(
+    glm::Mat3x2,
+    gl::FLOAT_MAT2x3,
+    UniformMatrix2x3fv,
+    f32,
+    gl::FALSE
+);
+impl_uniform!(
+    glm::Mat3x4,
+    gl::FLOAT_MAT4x3,
+    UniformMatrix4x3fv,
+    f32,
+    gl::FALSE
+);
+impl_uniform!(
+    glm::Mat4x2,
+    gl::FLOAT_MAT2x4,
+    UniformMatrix2x4fv,
+    f32,
+    gl::FALSE
+);
+impl_uniform!(
+    glm::Mat4x3,
+    gl::FLOAT_MAT3x4,
+    UniformMatrix3x4fv,
+    f32,
+    gl::FALSE
+);
+
+impl_uniform!(
+    glm::DMat2,
+    gl::DOUBLE_MAT2,
+    UniformMatrix2dv,
+    f64,
+    gl::FALSE
+);
+impl_uniform!(
+    glm::DMat3,
+    gl::DOUBLE_MAT3,
+    UniformMatrix3dv,
+    f64,
+    gl::FALSE
+);
+impl_uniform!(
+    glm::DMat4,
+    gl::DOUBLE_MAT4,
+    UniformMatrix4dv,
+    f64,
+    gl::FALSE
+);
+
+// bools are 4 bytes in GLSL, so they are uploaded through a converted copy
+impl Uniform for bool {
+    const GL_TYPE: GLenum = gl::BOOL;
+
+    fn upload(&self, location: i32) {
+        Self::upload_slice(location, std::slice::from_ref(self));
+    }
+}
+
+impl UniformElement for bool {
+    fn upload_slice(location: i32, values: &[Self]) {
+        let values: Vec<i32> = values.iter().map(|&b| i32::from(b)).collect();
+
+        unsafe {
+            gl::Uniform1iv(location, values.len() as i32, values.as_ptr());
+        }
+    }
+}
+
+/// Enumerates the active uniforms of a linked program.
+///
+/// Arrays are reachable both as `name` and `name[i]` for every element.
+/// Uniforms inside uniform blocks have no location and are skipped.
+pub(crate) fn reflect(program: u32) -> HashMap<String, UniformInfo> {
+    let mut uniforms = HashMap::new();
+
+    let mut count = 0;
+    let mut max_length = 0;
+
+    unsafe {
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_length);
+    }
+
+    let mut name = vec![0u8; max_length.max(1) as usize];
+
+    for index in 0..count as u32 {
+        let mut length = 0;
+        let mut size = 0;
+        let mut gl_type = 0;
+
+        unsafe {
+            gl::GetActiveUniform(
+                program,
+                index,
+                name.len() as i32,
+                &mut length,
+                &mut size,
+                &mut gl_type,
+                name.as_mut_ptr() as *mut GLchar,
+            );
+        }
+
+        let full_name = String::from_utf8_lossy(&name[..length as usize]).into_owned();
+
+        // arrays are reported as `name[0]`
+        let base = full_name.strip_suffix("[0]").unwrap_or(&full_name);
+
+        for element in 0..size {
+            let element_name = match size {
+                1 if base == full_name => base.to_owned(),
+                _ => format!("{base}[{element}]"),
+            };
+
+            let Some(location) = location(program, &element_name) else {
+                continue;
+            };
+
+            let info = UniformInfo {
+                location,
+                gl_type,
+                size: size - element,
+            };
+
+            if element == 0 {
+                uniforms.insert(base.to_owned(), info);
+            }
+
+            uniforms.insert(element_name, info);
+        }
+    }
+
+    uniforms
+}
+
+fn location(program: u32, name: &str) -> Option<i32> {
+    let c_str = CString::new(name).ok()?;
+
+    match unsafe { gl::GetUniformLocation(program, c_str.as_ptr()) } {
+        -1 => None,
+        location => Some(location),
+    }
+}
+
+// location to write `elements` values of `gl_type` to, GL would fail or drop
+// elements past the end of the array
+pub(crate) fn check(
+    name: &str,
+    info: &UniformInfo,
+    gl_type: GLenum,
+    elements: usize,
+) -> Result<i32> {
+    if !compatible(info.gl_type, gl_type) {
+        return Err(Error::UniformTypeMismatch {
+            name: name.to_owned(),
+            declared: info.gl_type,
+            provided: gl_type,
+        });
+    }
+
+    if elements > info.size as usize {
+        return Err(Error::UniformArrayLength {
+            name: name.to_owned(),
+            declared: info.size as usize,
+            provided: elements,
+        });
+    }
+
+    Ok(info.location)
+}
+
+/// Whether a value uploaded as `provided` can be written to a uniform
+/// declared as `declared`.
+///
+/// Besides exact matches, samplers and images are set with an `int`, and
+/// `bool` uniforms accept `int`, `uint` and `float` of the same width.
+pub fn compatible(declared: GLenum, provided: GLenum) -> bool {
+    if declared == provided {
+        return true;
+    }
+
+    if glsl_name(declared).is_none() {
+        return provided == gl::INT;
+    }
+
+    let bool_for = match provided {
+        gl::INT | gl::UNSIGNED_INT | gl::FLOAT => gl::BOOL,
+        gl::INT_VEC2 | gl::UNSIGNED_INT_VEC2 | gl::FLOAT_VEC2 => gl::BOOL_VEC2,
+        gl::INT_VEC3 | gl::UNSIGNED_INT_VEC3 | gl::FLOAT_VEC3 => gl::BOOL_VEC3,
+        gl::INT_VEC4 | gl::UNSIGNED_INT_VEC4 | gl::FLOAT_VEC4 => gl::BOOL_VEC4,
+        _ => return false,
+    };
+
+    declared == bool_for
+}
+
+/// GLSL name of a non-opaque uniform type, `None` for samplers, images and
+/// atomic counters.
+pub fn glsl_name(t: GLenum) -> Option<&'static str> {
+    Some(match t {
+        gl::FLOAT => "float",
+        gl::FLOAT_VEC2 => "vec2",
+        gl::FLOAT_VEC3 => "vec3",
+        gl::FLOAT_VEC4 => "vec4",
+        gl::DOUBLE => "double",
+        gl::DOUBLE_VEC2 => "dvec2",
+        gl::DOUBLE_VEC3 => "dvec3",
+        gl::DOUBLE_VEC4 => "dvec4",
+        gl::INT => "int",
+        gl::INT_VEC2 => "ivec2",
+        gl::INT_VEC3 => "ivec3",
+        gl::INT_VEC4 => "ivec4",
+        gl::UNSIGNED_INT => "uint",
+        gl::UNSIGNED_INT_VEC2 => "uvec2",
+        gl::UNSIGNED_INT_VEC3 => "uvec3",
+        gl::UNSIGNED_INT_VEC4 => "uvec4",
+        gl::BOOL => "bool",
+        gl::BOOL_VEC2 => "bvec2",
+        gl::BOOL_VEC3 => "bvec3",
+        gl::BOOL_VEC4 => "bvec4",
+        gl::FLOAT_MAT2 => "mat2",
+        gl::FLOAT_MAT3 => "mat3",
+        gl::FLOAT_MAT4 => "mat4",
+        gl::FLOAT_MAT2x3 => "mat2x3",
+        gl::FLOAT_MAT2x4 => "mat2x4",
+        gl::FLOAT_MAT3x2 => "mat3x2",
+        gl::FLOAT_MAT3x4 => "mat3x4",
+        gl::FLOAT_MAT4x2 => "mat4x2",
+        gl::FLOAT_MAT4x3 => "mat4x3",
+        gl::DOUBLE_MAT2 => "dmat2",
+        gl::DOUBLE_MAT3 => "dmat3",
+        gl::DOUBLE_MAT4 => "dmat4",
+        gl::DOUBLE_MAT2x3 => "dmat2x3",
+        gl::DOUBLE_MAT2x4 => "dmat2x4",
+        gl::DOUBLE_MAT3x2 => "dmat3x2",
+        gl::DOUBLE_MAT3x4 => "dmat3x4",
+        gl::DOUBLE_MAT4x2 => "dmat4x2",
+        gl::DOUBLE_MAT4x3 => "dmat4x3",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIGHTS: UniformInfo = UniformInfo {
+        location: 4,
+        gl_type: gl::FLOAT_VEC3,
+        size: 4,
+    };
+
+    #[test]
+    fn arrays_fit_the_declaration() {
+        let lights = [glm::Vec3::zeros(); 4];
+
+        assert_eq!(
+            check("lights", &LIGHTS, gl::FLOAT_VEC3, lights.elements()).unwrap(),
+            4
+        );
+        assert!(matches!(
+            check(
+                "lights",
+                &LIGHTS,
+                gl::FLOAT_VEC3,
+                [glm::Vec3::zeros(); 5].elements()
+            ),
+            Err(Error::UniformArrayLength {
+                declared: 4,
+                provided: 5,
+                ..
+            })
+        ));
+
+        // `lights[3]` only has room for the last element
+        let last = UniformInfo {
+            location: 7,
+            size: 1,
+            ..LIGHTS
+        };
+        assert!(check("lights[3]", &last, gl::FLOAT_VEC3, 2).is_err());
+    }
+
+    #[test]
+    fn slices_need_an_array() {
+        let scalar = UniformInfo {
+            location: 0,
+            gl_type: gl::FLOAT,
+            size: 1,
+        };
+
+        assert!(check("exposure", &scalar, gl::FLOAT, 1.0f32.elements()).is_ok());
+        assert!(matches!(
+            check("exposure", &scalar, gl::FLOAT, vec![1.0f32, 2.].elements()),
+            Err(Error::UniformArrayLength {
+                declared: 1,
+                provided: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn types_are_checked_first() {
+        assert!(matches!(
+            check("lights", &LIGHTS, gl::FLOAT_VEC4, 1),
+            Err(Error::UniformTypeMismatch { .. })
+        ));
+    }
+}
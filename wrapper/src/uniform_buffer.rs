@@ -0,0 +1,72 @@
+use std::marker::PhantomData;
+
+use crate::{
+    buffer::{Binding, Buffer, BufferType, DrawType, UNIFORM_BINDINGS},
+    error::{Error, Result},
+    layout::Std140,
+    shader_program::ShaderProgram,
+};
+
+/// A uniform buffer holding one `T` in `std140` layout, shared by every
+/// program it's [attached](UniformBuffer::attach) to.
+pub struct UniformBuffer<T: Std140> {
+    buffer: Buffer,
+    binding: Binding,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Std140> UniformBuffer<T> {
+    /// Creates the buffer on the lowest binding point not used by another
+    /// `UniformBuffer`.
+    pub fn new(value: &T) -> Self {
+        Self::create(Binding::acquire(&UNIFORM_BINDINGS), value)
+    }
+
+    /// Creates the buffer on a fixed binding point, e.g. one declared in GLSL
+    /// with `layout(binding = N)`. Fails if another `UniformBuffer` uses it.
+    pub fn with_binding(binding: u32, value: &T) -> Result<Self> {
+        Ok(Self::create(
+            Binding::reserve(&UNIFORM_BINDINGS, binding)?,
+            value,
+        ))
+    }
+
+    fn create(binding: Binding, value: &T) -> Self {
+        let mut buffer = Buffer::new(BufferType::Uniform);
+        // a new buffer has no immutable storage, and uniform buffers are indexed
+        buffer
+            .data_bytes(&value.std140_bytes(), DrawType::DynamicDraw)
+            .unwrap();
+        buffer.bind_base(binding.index()).unwrap();
+
+        Self {
+            buffer,
+            binding,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn binding(&self) -> u32 {
+        self.binding.index()
+    }
+
+    pub fn set(&self, value: &T) {
+        self.buffer.sub_data_bytes(0, &value.std140_bytes());
+    }
+
+    /// Connects the uniform block `name` of `program` to this buffer, checking
+    /// that the block has the same size as `T`.
+    pub fn attach(&self, program: &ShaderProgram, name: &str) -> Result<()> {
+        let size = program.uniform_block_size(name)?;
+
+        if size != T::SIZE {
+            return Err(Error::BlockSizeMismatch {
+                name: name.to_owned(),
+                block: size,
+                rust: T::SIZE,
+            });
+        }
+
+        program.bind_uniform_block(name, self.binding())
+    }
+}
@@ -1,14 +1,166 @@
 use std::ffi::c_void;
 
+use bytemuck::{Pod, Zeroable};
 use gl::types::{GLboolean, GLenum};
+use half::f16;
+
+use nalgebra_glm as glm;
+
+use crate::buffer::{Buffer, BufferType};
+
+pub use wrapper_derive::Vertex;
+
+/// A vertex type whose fields are read by the vertex shader as attributes,
+/// usually implemented with `#[derive(Vertex)]`.
+///
+/// ```ignore
+/// #[repr(C)]
+/// #[derive(Clone, Copy, Pod, Zeroable, Vertex)]
+/// struct ColoredVertex {
+///     position: glm::Vec3,
+///     #[vertex(normalized)]
+///     color: [u8; 4],
+/// }
+/// ```
+pub trait Vertex: Pod {
+    fn attributes() -> Vec<VertexAttribute>;
+
+    /// Position of the vertex in model space, used for bounding boxes.
+    fn position(&self) -> Option<Position> {
+        None
+    }
+}
+
+/// Return type of [`Vertex::position`].
+pub type Position = glm::Vec3;
+
+/// One attribute of a [`Vertex`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexAttribute {
+    pub location: u32,
+    pub components: i32,
+    pub gl_type: GLenum,
+    pub kind: AttributeKind,
+    /// Offset from the start of the vertex in bytes.
+    pub offset: usize,
+    /// Instances drawn per step through the attribute, 0 to step once per
+    /// vertex.
+    pub divisor: u32,
+}
+
+/// How the shader sees an attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeKind {
+    /// `float`/`vec`, integers converted as they are.
+    Float,
+    /// `float`/`vec`, integers mapped to `[0, 1]` (unsigned) or `[-1, 1]`.
+    Normalized,
+    /// `int`/`ivec`/`uint`/`uvec`, through `glVertexAttribIPointer`.
+    Integer,
+    /// `double`/`dvec`, through `glVertexAttribLPointer`.
+    Double,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive {
+    Points,
+    Lines,
+    LineStrip,
+    LineLoop,
+    Triangles,
+    TriangleStrip,
+    TriangleFan,
+    LinesAdjacency,
+    LineStripAdjacency,
+    TrianglesAdjacency,
+    TriangleStripAdjacency,
+    /// Patches of the given number of vertices, for tessellation.
+    Patches(i32),
+}
+
+impl Primitive {
+    pub fn resolve(&self) -> GLenum {
+        match self {
+            Self::Points => gl::POINTS,
+            Self::Lines => gl::LINES,
+            Self::LineStrip => gl::LINE_STRIP,
+            Self::LineLoop => gl::LINE_LOOP,
+            Self::Triangles => gl::TRIANGLES,
+            Self::TriangleStrip => gl::TRIANGLE_STRIP,
+            Self::TriangleFan => gl::TRIANGLE_FAN,
+            Self::LinesAdjacency => gl::LINES_ADJACENCY,
+            Self::LineStripAdjacency => gl::LINE_STRIP_ADJACENCY,
+            Self::TrianglesAdjacency => gl::TRIANGLES_ADJACENCY,
+            Self::TriangleStripAdjacency => gl::TRIANGLE_STRIP_ADJACENCY,
+            Self::Patches(_) => gl::PATCHES,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexType {
+    U8,
+    U16,
+    U32,
+}
+
+impl IndexType {
+    pub fn resolve(&self) -> GLenum {
+        match self {
+            Self::U8 => gl::UNSIGNED_BYTE,
+            Self::U16 => gl::UNSIGNED_SHORT,
+            Self::U32 => gl::UNSIGNED_INT,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            Self::U8 => 1,
+            Self::U16 => 2,
+            Self::U32 => 4,
+        }
+    }
+}
+
+/// A draw read from a `BufferType::DrawIndirect` buffer by
+/// [`VertexArray::multi_draw_indirect`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct DrawArraysCommand {
+    pub count: u32,
+    pub instance_count: u32,
+    pub first: u32,
+    pub base_instance: u32,
+}
+
+/// A draw read from a `BufferType::DrawIndirect` buffer by
+/// [`VertexArray::multi_draw_indexed_indirect`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct DrawElementsCommand {
+    pub count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub base_vertex: i32,
+    pub base_instance: u32,
+}
 
 pub struct VertexArray {
     pub id: u32,
+    vertex_count: usize,
+    // size of the element array buffer, if there is one
+    index_bytes: Option<usize>,
+    index_type: IndexType,
 }
 
 impl VertexArray {
     pub fn new() -> Self {
-        let mut arr = Self { id: 0 };
+        let mut arr = Self {
+            id: 0,
+            vertex_count: 0,
+            index_bytes: None,
+            index_type: IndexType::U32,
+        };
 
         unsafe {
             gl::GenVertexArrays(1, &mut arr.id);
@@ -29,6 +181,235 @@ impl VertexArray {
         }
     }
 
+    /// Creates a vertex array reading vertices of type `V` from `vbo`, and
+    /// `u32` indices from `ebo` if there is one (see
+    /// [`VertexArray::set_index_type`]). The vertex and index counts are taken
+    /// from the buffer sizes.
+    pub fn with_layout<V: Vertex>(vbo: &Buffer, ebo: Option<&Buffer>) -> Self {
+        let mut vao = Self::new();
+
+        vao.attach::<V>(vbo);
+        vao.vertex_count = vbo.size() / std::mem::size_of::<V>().max(1);
+
+        // the element array binding is part of the vertex array's state
+        if let Some(ebo) = ebo {
+            vao.bind();
+            ebo.bind();
+            Self::unbind();
+
+            vao.index_bytes = Some(ebo.size());
+        }
+
+        vao
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertex_count
+    }
+
+    /// Sets the number of vertices drawn by [`VertexArray::draw`], for vertex
+    /// arrays set up by hand or buffers that were resized.
+    pub fn set_vertex_count(&mut self, count: usize) {
+        self.vertex_count = count;
+    }
+
+    pub fn index_count(&self) -> usize {
+        self.index_bytes.unwrap_or(0) / self.index_type.size()
+    }
+
+    /// Sets the number of indices, and marks the vertex array as indexed.
+    pub fn set_index_count(&mut self, count: usize) {
+        self.index_bytes = Some(count * self.index_type.size());
+    }
+
+    pub fn index_type(&self) -> IndexType {
+        self.index_type
+    }
+
+    /// Changes the type the element array buffer is read as. The index count
+    /// is recomputed from the buffer size.
+    pub fn set_index_type(&mut self, t: IndexType) {
+        self.index_type = t;
+    }
+
+    /// Binds the vertex array and returns the mode to draw `primitive` with.
+    fn prepare(&self, primitive: Primitive) -> GLenum {
+        self.bind();
+
+        if let Primitive::Patches(vertices) = primitive {
+            unsafe {
+                gl::PatchParameteri(gl::PATCH_VERTICES, vertices);
+            }
+        }
+
+        primitive.resolve()
+    }
+
+    fn prepare_indexed(&self, primitive: Primitive) -> GLenum {
+        debug_assert!(self.index_bytes.is_some(), "vertex array has no indices");
+
+        self.prepare(primitive)
+    }
+
+    pub fn draw(&self, primitive: Primitive) {
+        let mode = self.prepare(primitive);
+
+        unsafe {
+            gl::DrawArrays(mode, 0, self.vertex_count as i32);
+        }
+    }
+
+    pub fn draw_instanced(&self, primitive: Primitive, instances: u32) {
+        let mode = self.prepare(primitive);
+
+        unsafe {
+            gl::DrawArraysInstanced(mode, 0, self.vertex_count as i32, instances as i32);
+        }
+    }
+
+    pub fn draw_indexed(&self, primitive: Primitive) {
+        let mode = self.prepare_indexed(primitive);
+
+        unsafe {
+            gl::DrawElements(
+                mode,
+                self.index_count() as i32,
+                self.index_type.resolve(),
+                std::ptr::null(),
+            );
+        }
+    }
+
+    pub fn draw_indexed_instanced(&self, primitive: Primitive, instances: u32) {
+        let mode = self.prepare_indexed(primitive);
+
+        unsafe {
+            gl::DrawElementsInstanced(
+                mode,
+                self.index_count() as i32,
+                self.index_type.resolve(),
+                std::ptr::null(),
+                instances as i32,
+            );
+        }
+    }
+
+    /// Draws `count` indices starting at index `first`, with `base_vertex`
+    /// added to each of them. Used to draw one of several meshes sharing the
+    /// same buffers.
+    pub fn draw_indexed_base_vertex(
+        &self,
+        primitive: Primitive,
+        first: usize,
+        count: usize,
+        base_vertex: i32,
+    ) {
+        self.draw_indexed_instanced_base_vertex(primitive, first, count, base_vertex, 1);
+    }
+
+    pub fn draw_indexed_instanced_base_vertex(
+        &self,
+        primitive: Primitive,
+        first: usize,
+        count: usize,
+        base_vertex: i32,
+        instances: u32,
+    ) {
+        debug_assert!(
+            first + count <= self.index_count(),
+            "index range out of bounds"
+        );
+
+        let mode = self.prepare_indexed(primitive);
+
+        unsafe {
+            gl::DrawElementsInstancedBaseVertex(
+                mode,
+                count as i32,
+                self.index_type.resolve(),
+                (first * self.index_type.size()) as *const c_void,
+                instances as i32,
+                base_vertex,
+            );
+        }
+    }
+
+    /// Issues `count` draws described by the [`DrawArraysCommand`]s in
+    /// `commands`, a `BufferType::DrawIndirect` buffer.
+    pub fn multi_draw_indirect(&self, primitive: Primitive, commands: &Buffer, count: usize) {
+        debug_assert!(matches!(commands.buffer_type(), BufferType::DrawIndirect));
+
+        let mode = self.prepare(primitive);
+        commands.bind();
+
+        unsafe {
+            gl::MultiDrawArraysIndirect(mode, std::ptr::null(), count as i32, 0);
+        }
+    }
+
+    /// Issues `count` draws described by the [`DrawElementsCommand`]s in
+    /// `commands`, a `BufferType::DrawIndirect` buffer.
+    pub fn multi_draw_indexed_indirect(
+        &self,
+        primitive: Primitive,
+        commands: &Buffer,
+        count: usize,
+    ) {
+        debug_assert!(matches!(commands.buffer_type(), BufferType::DrawIndirect));
+
+        let mode = self.prepare_indexed(primitive);
+        commands.bind();
+
+        unsafe {
+            gl::MultiDrawElementsIndirect(
+                mode,
+                self.index_type.resolve(),
+                std::ptr::null(),
+                count as i32,
+                0,
+            );
+        }
+    }
+
+    /// Adds the attributes of `V`, read from `vbo`. Used for a second buffer
+    /// with per-instance attributes, whose fields have a divisor.
+    pub fn attach<V: Vertex>(&self, vbo: &Buffer) {
+        let stride = std::mem::size_of::<V>() as i32;
+
+        self.bind();
+        vbo.bind();
+
+        for attribute in V::attributes() {
+            let (index, size, t) = (attribute.location, attribute.components, attribute.gl_type);
+            let pointer = attribute.offset as *const c_void;
+
+            unsafe {
+                match attribute.kind {
+                    AttributeKind::Float | AttributeKind::Normalized => gl::VertexAttribPointer(
+                        index,
+                        size,
+                        t,
+                        (attribute.kind == AttributeKind::Normalized) as GLboolean,
+                        stride,
+                        pointer,
+                    ),
+                    AttributeKind::Integer => {
+                        gl::VertexAttribIPointer(index, size, t, stride, pointer)
+                    }
+                    AttributeKind::Double => {
+                        gl::VertexAttribLPointer(index, size, t, stride, pointer)
+                    }
+                }
+
+                gl::VertexAttribDivisor(index, attribute.divisor);
+            }
+
+            Self::enable_vertex_attrib_array(index);
+        }
+
+        Self::unbind();
+    }
+
     pub fn vertex_atrrib_pointer<T: GlType>(
         index: u32,
         size: i32,
@@ -73,11 +454,120 @@ impl Drop for VertexArray {
 }
 
 pub trait GlType {
+    /// How attributes of this type are read unless the field says otherwise.
+    const KIND: AttributeKind;
+
     fn resolve() -> GLenum;
 }
 
-impl GlType for f32 {
-    fn resolve() -> GLenum {
-        gl::FLOAT
+macro_rules! impl_gl_type {
+    ($t:ty, $gl_type:expr, $kind:ident) => {
+        impl GlType for $t {
+            const KIND: AttributeKind = AttributeKind::$kind;
+
+            fn resolve() -> GLenum {
+                $gl_type
+            }
+        }
+    };
+}
+
+impl_gl_type!(f32, gl::FLOAT, Float);
+impl_gl_type!(f16, gl::HALF_FLOAT, Float);
+impl_gl_type!(f64, gl::DOUBLE, Double);
+impl_gl_type!(i8, gl::BYTE, Integer);
+impl_gl_type!(u8, gl::UNSIGNED_BYTE, Integer);
+impl_gl_type!(i16, gl::SHORT, Integer);
+impl_gl_type!(u16, gl::UNSIGNED_SHORT, Integer);
+impl_gl_type!(i32, gl::INT, Integer);
+impl_gl_type!(u32, gl::UNSIGNED_INT, Integer);
+
+/// A type that can be a field of a [`Vertex`]: a scalar, an array or a glm
+/// vector of up to 4 components, or a packed format.
+pub trait AttributeType {
+    const COMPONENTS: i32;
+    const KIND: AttributeKind;
+
+    fn gl_type() -> GLenum;
+}
+
+impl<T: GlType> AttributeType for T {
+    const COMPONENTS: i32 = 1;
+    const KIND: AttributeKind = T::KIND;
+
+    fn gl_type() -> GLenum {
+        T::resolve()
+    }
+}
+
+impl<T: GlType, const N: usize> AttributeType for [T; N] {
+    const COMPONENTS: i32 = N as i32;
+    const KIND: AttributeKind = T::KIND;
+
+    fn gl_type() -> GLenum {
+        T::resolve()
+    }
+}
+
+impl<T: GlType + glm::Scalar, const N: usize> AttributeType for glm::TVec<T, N> {
+    const COMPONENTS: i32 = N as i32;
+    const KIND: AttributeKind = T::KIND;
+
+    fn gl_type() -> GLenum {
+        T::resolve()
+    }
+}
+
+/// A `vec4` packed into 32 bits, 10 bits each for x, y and z and 2 for w, as
+/// signed normalized values. Commonly used for normals and tangents.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+pub struct Int2101010Rev(pub u32);
+
+impl Int2101010Rev {
+    /// Packs components in `[-1, 1]`, clamping the ones outside.
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        let pack = |v: f32, max: f32, bits: u32| {
+            ((v.clamp(-1.0, 1.0) * max).round() as i32 as u32) & ((1 << bits) - 1)
+        };
+
+        Self(
+            pack(x, 511.0, 10)
+                | pack(y, 511.0, 10) << 10
+                | pack(z, 511.0, 10) << 20
+                | pack(w, 1.0, 2) << 30,
+        )
+    }
+}
+
+impl AttributeType for Int2101010Rev {
+    const COMPONENTS: i32 = 4;
+    const KIND: AttributeKind = AttributeKind::Normalized;
+
+    fn gl_type() -> GLenum {
+        gl::INT_2_10_10_10_REV
+    }
+}
+
+/// Unsigned counterpart of [`Int2101010Rev`], e.g. for HDR colors.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+pub struct UInt2101010Rev(pub u32);
+
+impl UInt2101010Rev {
+    /// Packs components in `[0, 1]`, clamping the ones outside.
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        let pack = |v: f32, max: f32| (v.clamp(0.0, 1.0) * max).round() as u32;
+
+        Self(pack(x, 1023.0) | pack(y, 1023.0) << 10 | pack(z, 1023.0) << 20 | pack(w, 3.0) << 30)
+    }
+}
+
+impl AttributeType for UInt2101010Rev {
+    const COMPONENTS: i32 = 4;
+    const KIND: AttributeKind = AttributeKind::Normalized;
+
+    fn gl_type() -> GLenum {
+        gl::UNSIGNED_INT_2_10_10_10_REV
     }
 }